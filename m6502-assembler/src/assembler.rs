@@ -0,0 +1,536 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use m6502::{
+    addressing_modes::AddressingMode,
+    instructions::Instruction,
+    opcode_table::{decode, encode, OpcodeEntry},
+    variant::Variant,
+};
+
+use crate::{
+    error::{AssemblyError, ErrorKind, Location},
+    expression::Expr,
+    parser::{parse_line, Body, ByteItem, Line, Operand},
+};
+
+/// Turns 6502 source into machine code
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Assembler {
+    /// Chip whose opcodes are available
+    pub variant: Variant,
+    /// Whether undocumented NMOS opcodes can be used
+    pub undocumented_opcodes: bool,
+}
+
+/// What a symbol was defined as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// `name:`, the address of a line
+    Label,
+    /// `name = value`
+    Constant,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub value: i64,
+    pub kind: SymbolKind,
+}
+
+/// Assembled machine code
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+    /// Address of the first byte
+    pub origin: u16,
+    /// The code and data from `origin` on, with gaps between `.org` blocks filled with zeros
+    pub bytes: Vec<u8>,
+    /// All symbols, with local labels named `scope@label`
+    pub symbols: BTreeMap<String, Symbol>,
+}
+
+impl Program {
+    /// Names of addresses, for the disassembler. Where several labels share an address, the
+    /// first in alphabetical order is used.
+    pub fn labels(&self) -> BTreeMap<u16, String> {
+        let mut labels = BTreeMap::new();
+        for (name, symbol) in &self.symbols {
+            if symbol.kind == SymbolKind::Label {
+                labels
+                    .entry(symbol.value as u16)
+                    .or_insert_with(|| name.clone());
+            }
+        }
+        labels
+    }
+}
+
+/// Assembles `source` for the NMOS 6502 with documented opcodes only.
+pub fn assemble(source: &str) -> Result<Program, AssemblyError> {
+    Assembler::new().assemble(source)
+}
+
+/// A source line, ready to assemble
+struct Statement {
+    /// Where the line's instruction or directive starts
+    location: Location,
+    line: Line,
+}
+
+impl Statement {
+    fn error(&self, kind: ErrorKind) -> AssemblyError {
+        AssemblyError {
+            location: self.location.clone(),
+            kind,
+        }
+    }
+
+    /// Turns an error from an expression into one at the right column.
+    fn at_column(&self, (kind, column): (ErrorKind, usize)) -> AssemblyError {
+        AssemblyError {
+            location: Location {
+                column,
+                ..self.location.clone()
+            },
+            kind,
+        }
+    }
+}
+
+/// Symbols defined so far
+#[derive(Default)]
+struct Symbols {
+    values: HashMap<String, i64>,
+    kinds: BTreeMap<String, SymbolKind>,
+}
+
+impl Symbols {
+    fn define(
+        &mut self,
+        statement: &Statement,
+        name: &str,
+        value: i64,
+        kind: SymbolKind,
+    ) -> Result<(), AssemblyError> {
+        if self.kinds.insert(name.into(), kind).is_some() {
+            return Err(statement.error(ErrorKind::DuplicateSymbol(name.into())));
+        }
+        self.values.insert(name.into(), value);
+        Ok(())
+    }
+}
+
+/// Opcodes available for encoding
+struct Encodings {
+    mnemonics: HashMap<String, Instruction>,
+    opcodes: HashMap<(Instruction, AddressingMode), OpcodeEntry>,
+}
+
+impl Encodings {
+    fn new(assembler: &Assembler) -> Self {
+        let (variant, undocumented) = (assembler.variant, assembler.undocumented_opcodes);
+        let mut encodings = Self {
+            mnemonics: HashMap::new(),
+            opcodes: HashMap::new(),
+        };
+        for entry in (0..=255).filter_map(|code| decode(variant, undocumented, code)) {
+            let key = (entry.instruction, entry.addressing_mode);
+            encodings
+                .mnemonics
+                .insert(format!("{:?}", entry.instruction), entry.instruction);
+            encodings
+                .opcodes
+                .entry(key)
+                .or_insert_with(|| encode(variant, undocumented, key.0, key.1).unwrap_or(entry));
+        }
+        encodings
+    }
+
+    /// Picks the opcode for an instruction. Zero page forms are used for addresses known to
+    /// be below $100 by now.
+    fn choose(
+        &self,
+        mnemonic: &str,
+        operand: &Operand,
+        symbols: &HashMap<String, i64>,
+        pc: u16,
+    ) -> Result<OpcodeEntry, ErrorKind> {
+        use AddressingMode::*;
+
+        let unknown = || ErrorKind::UnknownMnemonic(mnemonic.into());
+        let instruction = *self.mnemonics.get(mnemonic).ok_or_else(unknown)?;
+        let zero_page = |expr: &Expr| matches!(expr.eval(symbols, pc), Ok(value) if (0..0x100).contains(&value));
+        let pick = |expr: &Expr, short, long| {
+            if zero_page(expr) {
+                [short, long]
+            } else {
+                [long, short]
+            }
+        };
+        let candidates = match operand {
+            Operand::None | Operand::Accumulator => [Implied, Implied],
+            Operand::Immediate(_) => [Immediate, Immediate],
+            Operand::Direct(_) if self.opcodes.contains_key(&(instruction, Relative)) => {
+                [Relative, Relative]
+            }
+            Operand::Direct(expr) => pick(expr, ZeroPage, Absolute),
+            Operand::IndexedX(expr) => pick(expr, ZeroPageX, AbsoluteX),
+            Operand::IndexedY(expr) => pick(expr, ZeroPageY, AbsoluteY),
+            // No instruction has both forms of each of these.
+            Operand::Indirect(_) => [Indirect, ZeroPageIndirect],
+            Operand::IndirectX(_) => [IndirectX, AbsoluteIndirectX],
+            Operand::IndirectY(_) => [IndirectY, IndirectY],
+            Operand::Pair(_, _) => [ZeroPageRelative, ZeroPageRelative],
+        };
+        candidates
+            .iter()
+            .find_map(|mode| self.opcodes.get(&(instruction, *mode)).copied())
+            .ok_or_else(|| ErrorKind::InvalidAddressingMode {
+                mnemonic: mnemonic.into(),
+                operand: operand.describe(),
+            })
+    }
+}
+
+/// Checks that `value` fits in the range and returns it truncated to 16 bits.
+fn fit(value: i64, range: std::ops::RangeInclusive<i64>, bits: u8) -> Result<u16, ErrorKind> {
+    if range.contains(&value) {
+        Ok(value as u16)
+    } else {
+        Err(ErrorKind::OutOfRange { value, bits })
+    }
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An assembler for the opcodes of `variant`
+    pub fn with_variant(variant: Variant) -> Self {
+        Self {
+            variant,
+            ..Self::default()
+        }
+    }
+
+    /// Assembles source text. Errors refer to it as `<source>`, and `.include` paths are
+    /// relative to the working directory.
+    pub fn assemble(&self, source: &str) -> Result<Program, AssemblyError> {
+        let mut statements = Vec::new();
+        self.load(
+            source,
+            "<source>",
+            Path::new("."),
+            &mut Vec::new(),
+            &mut None,
+            &mut statements,
+        )?;
+        self.assemble_statements(&statements)
+    }
+
+    /// Assembles a source file. `.include` paths are relative to the including file.
+    pub fn assemble_file(&self, path: impl AsRef<Path>) -> Result<Program, AssemblyError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let error = |message: String| AssemblyError {
+            location: Location {
+                file: name.clone(),
+                line: 0,
+                column: 0,
+            },
+            kind: ErrorKind::Include {
+                path: name.clone(),
+                message,
+            },
+        };
+        let source = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        let mut stack = vec![path.canonicalize().map_err(|e| error(e.to_string()))?];
+        let mut statements = Vec::new();
+        self.load(
+            &source,
+            &name,
+            path.parent().unwrap_or(Path::new(".")),
+            &mut stack,
+            &mut None,
+            &mut statements,
+        )?;
+        self.assemble_statements(&statements)
+    }
+
+    /// Parses source, replacing `.include` lines with the included files' lines.
+    /// `stack` holds the files being included, to catch cycles.
+    fn load(
+        &self,
+        source: &str,
+        file: &str,
+        dir: &Path,
+        stack: &mut Vec<PathBuf>,
+        scope: &mut Option<String>,
+        statements: &mut Vec<Statement>,
+    ) -> Result<(), AssemblyError> {
+        for (index, text) in source.lines().enumerate() {
+            let location = |column| Location {
+                file: file.into(),
+                line: index + 1,
+                column,
+            };
+            let mut line = parse_line(text, scope).map_err(|(kind, column)| AssemblyError {
+                location: location(column),
+                kind,
+            })?;
+            let Body::Include(path) = line.body else {
+                statements.push(Statement {
+                    location: location(line.column),
+                    line,
+                });
+                continue;
+            };
+            let error = |kind| AssemblyError {
+                location: location(line.column),
+                kind,
+            };
+            let include_error = |e: std::io::Error| {
+                error(ErrorKind::Include {
+                    path: path.clone(),
+                    message: e.to_string(),
+                })
+            };
+            let full = dir.join(&path);
+            let canonical = full.canonicalize().map_err(include_error)?;
+            if stack.contains(&canonical) {
+                return Err(error(ErrorKind::IncludeCycle(path)));
+            }
+            let included = fs::read_to_string(&full).map_err(include_error)?;
+            // The label of the line, if any, stays with it.
+            line.body = Body::Empty;
+            statements.push(Statement {
+                location: location(line.column),
+                line,
+            });
+            stack.push(canonical);
+            self.load(
+                &included,
+                &path,
+                full.parent().unwrap_or(dir),
+                stack,
+                scope,
+                statements,
+            )?;
+            stack.pop();
+        }
+        Ok(())
+    }
+
+    fn assemble_statements(&self, statements: &[Statement]) -> Result<Program, AssemblyError> {
+        let encodings = Encodings::new(self);
+        let mut symbols = Symbols::default();
+
+        // Pass 1: place every line and define labels. Constants that refer to later labels
+        // are resolved once all labels are known.
+        let mut addresses = Vec::with_capacity(statements.len());
+        let mut opcodes = Vec::with_capacity(statements.len());
+        let mut deferred = Vec::new();
+        let mut pc: u32 = 0;
+        for (index, statement) in statements.iter().enumerate() {
+            if let Some(label) = &statement.line.label {
+                symbols.define(statement, label, pc as i64, SymbolKind::Label)?;
+            }
+            let mut opcode = None;
+            let size = match &statement.line.body {
+                Body::Empty | Body::Include(_) => 0,
+                Body::Constant { name, value } => {
+                    match value.eval(&symbols.values, pc as u16) {
+                        Ok(value) => {
+                            symbols.define(statement, name, value, SymbolKind::Constant)?
+                        }
+                        Err((ErrorKind::UndefinedSymbol(_), _)) => deferred.push(index),
+                        Err(e) => return Err(statement.at_column(e)),
+                    }
+                    0
+                }
+                Body::Org(address) => {
+                    let address =
+                        address
+                            .eval(&symbols.values, pc as u16)
+                            .map_err(|e| match e {
+                                (ErrorKind::UndefinedSymbol(name), column) => {
+                                    statement.at_column((ErrorKind::ForwardReference(name), column))
+                                }
+                                e => statement.at_column(e),
+                            })?;
+                    pc = fit(address, 0..=0xFFFF, 16).map_err(|e| statement.error(e))? as u32;
+                    0
+                }
+                Body::Bytes(items) => items
+                    .iter()
+                    .map(|item| match item {
+                        ByteItem::Value(_) => 1,
+                        ByteItem::String(string) => string.chars().count() as u32,
+                    })
+                    .sum(),
+                Body::Words(items) => 2 * items.len() as u32,
+                Body::Instruction { mnemonic, operand } => {
+                    let entry = encodings
+                        .choose(mnemonic, operand, &symbols.values, pc as u16)
+                        .map_err(|e| statement.error(e))?;
+                    opcode = Some(entry);
+                    entry.bytes as u32
+                }
+            };
+            addresses.push(pc as u16);
+            opcodes.push(opcode);
+            pc += size;
+            if pc > 0x10000 {
+                return Err(statement.error(ErrorKind::AddressOverflow));
+            }
+        }
+        while !deferred.is_empty() {
+            let mut remaining = Vec::new();
+            let mut first_error = None;
+            for &index in &deferred {
+                let statement = &statements[index];
+                let Body::Constant { name, value } = &statement.line.body else {
+                    unreachable!()
+                };
+                match value.eval(&symbols.values, addresses[index]) {
+                    Ok(value) => symbols.define(statement, name, value, SymbolKind::Constant)?,
+                    Err(e) => {
+                        first_error.get_or_insert_with(|| statement.at_column(e));
+                        remaining.push(index);
+                    }
+                }
+            }
+            if remaining.len() == deferred.len() {
+                return Err(first_error.unwrap());
+            }
+            deferred = remaining;
+        }
+
+        // Pass 2: emit the bytes.
+        let mut blocks: Vec<(u16, Vec<u8>)> = Vec::new();
+        // Addresses already emitted, so that an `.org` back over them is caught
+        let mut emitted = vec![false; 0x10000];
+        for ((statement, &pc), opcode) in statements.iter().zip(&addresses).zip(&opcodes) {
+            let eval = |expr: &Expr| {
+                expr.eval(&symbols.values, pc)
+                    .map_err(|e| statement.at_column(e))
+            };
+            let range_error = |e| statement.error(e);
+            let mut bytes = Vec::new();
+            match &statement.line.body {
+                Body::Empty | Body::Include(_) | Body::Constant { .. } => continue,
+                Body::Org(_) => {
+                    blocks.push((pc, Vec::new()));
+                    continue;
+                }
+                Body::Bytes(items) => {
+                    for item in items {
+                        match item {
+                            ByteItem::Value(expr) => bytes
+                                .push(fit(eval(expr)?, -128..=0xFF, 8).map_err(range_error)? as u8),
+                            ByteItem::String(string) => {
+                                for c in string.chars() {
+                                    if !c.is_ascii() {
+                                        return Err(statement.error(ErrorKind::NotAscii(c)));
+                                    }
+                                    bytes.push(c as u8);
+                                }
+                            }
+                        }
+                    }
+                }
+                Body::Words(items) => {
+                    for expr in items {
+                        let word = fit(eval(expr)?, -0x8000..=0xFFFF, 16).map_err(range_error)?;
+                        bytes.extend(word.to_le_bytes());
+                    }
+                }
+                Body::Instruction { operand, .. } => {
+                    let entry = opcode.unwrap();
+                    bytes.push(entry.code);
+                    let next = pc as i64 + entry.bytes as i64;
+                    let branch = |target: &Expr| {
+                        let offset = eval(target)? - next;
+                        if (-128..=127).contains(&offset) {
+                            Ok(offset as u8)
+                        } else {
+                            Err(statement.error(ErrorKind::BranchOutOfRange(offset)))
+                        }
+                    };
+                    match (operand, entry.addressing_mode) {
+                        (Operand::None | Operand::Accumulator, _) => {}
+                        (Operand::Immediate(expr), _) => {
+                            bytes.push(fit(eval(expr)?, -128..=0xFF, 8).map_err(range_error)? as u8)
+                        }
+                        (Operand::Direct(target), AddressingMode::Relative) => {
+                            bytes.push(branch(target)?)
+                        }
+                        (Operand::Pair(address, target), _) => {
+                            bytes
+                                .push(fit(eval(address)?, 0..=0xFF, 8).map_err(range_error)? as u8);
+                            bytes.push(branch(target)?);
+                        }
+                        (
+                            Operand::Direct(address)
+                            | Operand::IndexedX(address)
+                            | Operand::IndexedY(address)
+                            | Operand::Indirect(address)
+                            | Operand::IndirectX(address)
+                            | Operand::IndirectY(address),
+                            _,
+                        ) => {
+                            let address = eval(address)?;
+                            if entry.bytes == 2 {
+                                bytes.push(fit(address, 0..=0xFF, 8).map_err(range_error)? as u8);
+                            } else {
+                                let word = fit(address, 0..=0xFFFF, 16).map_err(range_error)?;
+                                bytes.extend(word.to_le_bytes());
+                            }
+                        }
+                    }
+                }
+            }
+            let range = pc as usize..pc as usize + bytes.len();
+            if let Some(addr) = range.clone().find(|&addr| emitted[addr]) {
+                return Err(statement.error(ErrorKind::Overwrite(addr as u16)));
+            }
+            emitted[range].fill(true);
+            match blocks.last_mut() {
+                Some((start, block)) if *start as usize + block.len() == pc as usize => {
+                    block.extend(bytes)
+                }
+                _ => blocks.push((pc, bytes)),
+            }
+        }
+
+        let mut program = Program {
+            origin: blocks
+                .iter()
+                .filter(|(_, block)| !block.is_empty())
+                .map(|(start, _)| *start)
+                .min()
+                .unwrap_or_default(),
+            ..Program::default()
+        };
+        for (start, block) in blocks {
+            if block.is_empty() {
+                continue;
+            }
+            let offset = (start - program.origin) as usize;
+            let end = offset + block.len();
+            if program.bytes.len() < end {
+                program.bytes.resize(end, 0);
+            }
+            program.bytes[offset..end].copy_from_slice(&block);
+        }
+        for (name, kind) in symbols.kinds {
+            let value = symbols.values[&name];
+            program.symbols.insert(name, Symbol { value, kind });
+        }
+        Ok(program)
+    }
+}
@@ -0,0 +1,65 @@
+use thiserror::Error;
+
+/// Where in the source something is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// Name of the source file, as given to the assembler or to `.include`
+    pub file: String,
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Column number in characters, starting at 1
+    pub column: usize,
+}
+
+/// An error in the source, with where it was found
+#[derive(Error, Debug)]
+#[error("{}:{}:{}: {kind}", location.file, location.line, location.column)]
+pub struct AssemblyError {
+    pub location: Location,
+    pub kind: ErrorKind,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    #[error("Unexpected character {0:?}.")]
+    UnexpectedCharacter(char),
+    #[error("Invalid number {0:?}.")]
+    InvalidNumber(String),
+    #[error("Unterminated string or character literal.")]
+    Unterminated,
+    #[error("{0:?} isn't ASCII, so it has no byte.")]
+    NotAscii(char),
+    #[error("Expected {expected}, found {found}.")]
+    Expected { expected: String, found: String },
+    #[error("Unknown instruction {0:?}.")]
+    UnknownMnemonic(String),
+    #[error("Unknown directive {0:?}.")]
+    UnknownDirective(String),
+    #[error("{mnemonic} can't be used with {operand}.")]
+    InvalidAddressingMode {
+        mnemonic: String,
+        operand: &'static str,
+    },
+    #[error("Local label {0:?} used before any global label.")]
+    LocalWithoutScope(String),
+    #[error("Symbol {0:?} is not defined.")]
+    UndefinedSymbol(String),
+    #[error("Symbol {0:?} is already defined.")]
+    DuplicateSymbol(String),
+    #[error("Symbol {0:?} has to be defined before this line.")]
+    ForwardReference(String),
+    #[error("Division by zero.")]
+    DivisionByZero,
+    #[error("Value {value} doesn't fit in {bits} bits.")]
+    OutOfRange { value: i64, bits: u8 },
+    #[error("Branch target is {0} bytes away, but branches reach -128 to 127.")]
+    BranchOutOfRange(i64),
+    #[error("Code runs past $FFFF.")]
+    AddressOverflow,
+    #[error("Overwrites the byte already assembled at ${0:04X}.")]
+    Overwrite(u16),
+    #[error("Can't include {path:?}: {message}")]
+    Include { path: String, message: String },
+    #[error("{0:?} includes itself.")]
+    IncludeCycle(String),
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use crate::error::ErrorKind;
+
+#[derive(Copy, Clone, Debug)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+    /// `<`: the low byte
+    LowByte,
+    /// `>`: the high byte
+    HighByte,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Number(i64),
+    /// A symbol, with local labels already qualified by their scope
+    Symbol {
+        name: String,
+        column: usize,
+    },
+    /// `*`, the address of the current line
+    CurrentAddress,
+    Unary(UnaryOp, Box<Expr>),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+        column: usize,
+    },
+}
+
+impl Expr {
+    /// Evaluates the expression. On error, also returns the column of the culprit.
+    pub fn eval(&self, symbols: &HashMap<String, i64>, pc: u16) -> Result<i64, (ErrorKind, usize)> {
+        Ok(match self {
+            Expr::Number(value) => *value,
+            Expr::Symbol { name, column } => match symbols.get(name) {
+                Some(value) => *value,
+                None => return Err((ErrorKind::UndefinedSymbol(name.clone()), *column)),
+            },
+            Expr::CurrentAddress => pc as i64,
+            Expr::Unary(op, operand) => {
+                let value = operand.eval(symbols, pc)?;
+                match op {
+                    UnaryOp::Negate => value.wrapping_neg(),
+                    UnaryOp::Not => !value,
+                    UnaryOp::LowByte => value & 0xFF,
+                    UnaryOp::HighByte => (value >> 8) & 0xFF,
+                }
+            }
+            Expr::Binary {
+                op,
+                lhs,
+                rhs,
+                column,
+            } => {
+                let lhs = lhs.eval(symbols, pc)?;
+                let rhs = rhs.eval(symbols, pc)?;
+                match op {
+                    BinaryOp::Add => lhs.wrapping_add(rhs),
+                    BinaryOp::Subtract => lhs.wrapping_sub(rhs),
+                    BinaryOp::Multiply => lhs.wrapping_mul(rhs),
+                    BinaryOp::Divide if rhs == 0 => {
+                        return Err((ErrorKind::DivisionByZero, *column))
+                    }
+                    BinaryOp::Divide => lhs.wrapping_div(rhs),
+                    BinaryOp::And => lhs & rhs,
+                    BinaryOp::Or => lhs | rhs,
+                    BinaryOp::Xor => lhs ^ rhs,
+                    BinaryOp::ShiftLeft => lhs.checked_shl(rhs as u32).unwrap_or(0),
+                    BinaryOp::ShiftRight => lhs.checked_shr(rhs as u32).unwrap_or(0),
+                }
+            }
+        })
+    }
+}
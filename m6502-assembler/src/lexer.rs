@@ -0,0 +1,120 @@
+use crate::error::ErrorKind;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A mnemonic, directive (starting with `.`), symbol or local label (starting with `@`)
+    Identifier(String),
+    /// A number or character literal
+    Number(i64),
+    /// A string literal
+    String(String),
+    /// An operator or punctuation
+    Punct(&'static str),
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Column of the first character, starting at 1
+    pub column: usize,
+}
+
+impl Token {
+    pub fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.kind, TokenKind::Punct(p) if p == punct)
+    }
+
+    /// How the token is shown in error messages
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            TokenKind::Identifier(name) => format!("{name:?}"),
+            TokenKind::Number(value) => value.to_string(),
+            TokenKind::String(string) => format!("string {string:?}"),
+            TokenKind::Punct(punct) => format!("\"{punct}\""),
+        }
+    }
+}
+
+const PUNCTUATION: &[&str] = &[
+    "<<", ">>", "#", "(", ")", ",", ":", "=", "+", "-", "*", "/", "&", "|", "^", "~", "<", ">",
+];
+
+/// Splits a line into tokens, dropping the comment after `;`.
+///
+/// On error, returns the column where it was found along with it.
+pub fn tokenize(line: &str) -> Result<Vec<Token>, (ErrorKind, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let start = i;
+        let kind = match c {
+            ';' => break,
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == '_' || c == '.' || c == '@' => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                TokenKind::Identifier(chars[start..i].iter().collect())
+            }
+            '$' | '%' | '0'..='9' => {
+                let radix = match c {
+                    '$' => 16,
+                    '%' => 2,
+                    _ => 10,
+                };
+                if radix != 10 {
+                    i += 1;
+                }
+                let digits_start = i;
+                while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                let digits: String = chars[digits_start..i].iter().collect();
+                match i64::from_str_radix(&digits, radix) {
+                    Ok(value) if value <= u32::MAX as i64 => TokenKind::Number(value),
+                    _ => {
+                        let text = chars[start..i].iter().collect();
+                        return Err((ErrorKind::InvalidNumber(text), column));
+                    }
+                }
+            }
+            '\'' => match (chars.get(i + 1), chars.get(i + 2)) {
+                (Some(&value), Some('\'')) => {
+                    i += 3;
+                    TokenKind::Number(value as i64)
+                }
+                _ => return Err((ErrorKind::Unterminated, column)),
+            },
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err((ErrorKind::Unterminated, column));
+                }
+                i += 1;
+                TokenKind::String(chars[start + 1..i - 1].iter().collect())
+            }
+            _ => {
+                let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                match PUNCTUATION.iter().find(|punct| rest.starts_with(**punct)) {
+                    Some(punct) => {
+                        i += punct.len();
+                        TokenKind::Punct(punct)
+                    }
+                    None => return Err((ErrorKind::UnexpectedCharacter(c), column)),
+                }
+            }
+        };
+        tokens.push(Token { kind, column });
+    }
+    Ok(tokens)
+}
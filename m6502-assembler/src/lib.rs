@@ -0,0 +1,13 @@
+pub use assembler::{assemble, Assembler, Program, Symbol, SymbolKind};
+pub use error::{AssemblyError, ErrorKind, Location};
+
+/// Two-pass assembly into a program and its symbols
+mod assembler;
+/// Assembly errors and where they were found
+pub mod error;
+/// Operand expressions
+mod expression;
+/// Splitting lines into tokens
+mod lexer;
+/// Parsing lines into statements
+mod parser;
@@ -0,0 +1,405 @@
+use crate::{
+    error::ErrorKind,
+    expression::{BinaryOp, Expr, UnaryOp},
+    lexer::{tokenize, Token, TokenKind},
+};
+
+/// The operand of an instruction, as written
+#[derive(Clone, Debug)]
+pub enum Operand {
+    None,
+    /// `A`
+    Accumulator,
+    /// `#value`
+    Immediate(Expr),
+    /// `address`
+    Direct(Expr),
+    /// `address,X`
+    IndexedX(Expr),
+    /// `address,Y`
+    IndexedY(Expr),
+    /// `(address)`
+    Indirect(Expr),
+    /// `(address,X)`
+    IndirectX(Expr),
+    /// `(address),Y`
+    IndirectY(Expr),
+    /// `address,target` of the Rockwell BBR and BBS
+    Pair(Expr, Expr),
+}
+
+impl Operand {
+    /// How the operand is shown in error messages
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Operand::None => "no operand",
+            Operand::Accumulator => "the accumulator",
+            Operand::Immediate(_) => "an immediate operand",
+            Operand::Direct(_) => "an address",
+            Operand::IndexedX(_) => "an address indexed by X",
+            Operand::IndexedY(_) => "an address indexed by Y",
+            Operand::Indirect(_) => "an indirect address",
+            Operand::IndirectX(_) => "an indexed indirect address",
+            Operand::IndirectY(_) => "an indirect indexed address",
+            Operand::Pair(_, _) => "an address and a branch target",
+        }
+    }
+}
+
+/// An item of `.byte`
+#[derive(Clone, Debug)]
+pub enum ByteItem {
+    Value(Expr),
+    /// Each character becomes a byte.
+    String(String),
+}
+
+/// What a line does, besides defining its label
+#[derive(Clone, Debug)]
+pub enum Body {
+    Empty,
+    Instruction {
+        mnemonic: String,
+        operand: Operand,
+    },
+    /// `.org address`
+    Org(Expr),
+    /// `.byte` or `.db`
+    Bytes(Vec<ByteItem>),
+    /// `.word` or `.dw`
+    Words(Vec<Expr>),
+    /// `name = value`
+    Constant {
+        name: String,
+        value: Expr,
+    },
+    /// `.include "file"`
+    Include(String),
+}
+
+/// A parsed line
+#[derive(Clone, Debug)]
+pub struct Line {
+    /// The label defined on the line, with local labels qualified by their scope
+    pub label: Option<String>,
+    pub body: Body,
+    /// Column of the body, starting at 1
+    pub column: usize,
+}
+
+/// Parses one line of source. `scope` is the last global label, which local labels (starting
+/// with `@`) belong to; it's updated when the line defines a global label.
+///
+/// On error, returns the column where it was found along with it.
+pub fn parse_line(line: &str, scope: &mut Option<String>) -> Result<Line, (ErrorKind, usize)> {
+    let tokens = tokenize(line)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        end_column: line.chars().count() + 1,
+        scope,
+    };
+    parser.line()
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    /// Column reported for errors at the end of the line
+    end_column: usize,
+    scope: &'a mut Option<String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn column(&self) -> usize {
+        self.peek().map_or(self.end_column, |token| token.column)
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.tokens.len()
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if self.peek().is_some_and(|token| token.is_punct(punct)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes a register name (`X` or `Y`, in any case) if it's next.
+    fn eat_register(&mut self, register: &str) -> bool {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Identifier(name),
+                ..
+            }) if name.eq_ignore_ascii_case(register) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T, (ErrorKind, usize)> {
+        let found = self
+            .peek()
+            .map_or_else(|| "end of line".into(), Token::describe);
+        let expected = expected.into();
+        Err((ErrorKind::Expected { expected, found }, self.column()))
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), (ErrorKind, usize)> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            self.error(&format!("\"{punct}\""))
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), (ErrorKind, usize)> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            self.error("end of line")
+        }
+    }
+
+    /// Qualifies a local label with its scope.
+    fn qualify(&self, name: &str, column: usize) -> Result<String, (ErrorKind, usize)> {
+        if !name.starts_with('@') {
+            return Ok(name.into());
+        }
+        match self.scope.as_ref() {
+            Some(scope) => Ok(format!("{scope}{name}")),
+            None => Err((ErrorKind::LocalWithoutScope(name.into()), column)),
+        }
+    }
+
+    fn line(&mut self) -> Result<Line, (ErrorKind, usize)> {
+        let mut label = None;
+        if let (Some(first), Some(second)) = (self.peek(), self.peek_at(1)) {
+            if let TokenKind::Identifier(name) = &first.kind {
+                if second.is_punct("=") {
+                    let name = self.qualify(name, first.column)?;
+                    let column = first.column;
+                    self.pos += 2;
+                    let value = self.expression()?;
+                    self.expect_end()?;
+                    return Ok(Line {
+                        label: None,
+                        body: Body::Constant { name, value },
+                        column,
+                    });
+                }
+                if second.is_punct(":") && !name.starts_with('.') {
+                    let qualified = self.qualify(name, first.column)?;
+                    if !name.starts_with('@') {
+                        *self.scope = Some(name.clone());
+                    }
+                    label = Some(qualified);
+                    self.pos += 2;
+                }
+            }
+        }
+        let column = self.column();
+        let body = match self.peek().map(|token| token.kind.clone()) {
+            None => Body::Empty,
+            Some(TokenKind::Identifier(name)) if name.starts_with('.') => {
+                self.pos += 1;
+                self.directive(&name, column)?
+            }
+            Some(TokenKind::Identifier(name)) => {
+                self.pos += 1;
+                Body::Instruction {
+                    mnemonic: name.to_ascii_uppercase(),
+                    operand: self.operand()?,
+                }
+            }
+            Some(_) => return self.error("an instruction or directive"),
+        };
+        self.expect_end()?;
+        Ok(Line {
+            label,
+            body,
+            column,
+        })
+    }
+
+    fn directive(&mut self, name: &str, column: usize) -> Result<Body, (ErrorKind, usize)> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            ".org" => Body::Org(self.expression()?),
+            ".byte" | ".db" => {
+                let mut items = Vec::new();
+                loop {
+                    if let Some(TokenKind::String(string)) = self.peek().map(|token| &token.kind) {
+                        items.push(ByteItem::String(string.clone()));
+                        self.pos += 1;
+                    } else {
+                        items.push(ByteItem::Value(self.expression()?));
+                    }
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                Body::Bytes(items)
+            }
+            ".word" | ".dw" => {
+                let mut items = vec![self.expression()?];
+                while self.eat(",") {
+                    items.push(self.expression()?);
+                }
+                Body::Words(items)
+            }
+            ".include" => match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::String(path)) => {
+                    let path = path.clone();
+                    self.pos += 1;
+                    Body::Include(path)
+                }
+                _ => return self.error("a file name in quotes"),
+            },
+            _ => return Err((ErrorKind::UnknownDirective(name.into()), column)),
+        })
+    }
+
+    fn operand(&mut self) -> Result<Operand, (ErrorKind, usize)> {
+        if self.at_end() {
+            return Ok(Operand::None);
+        }
+        if self.tokens.len() == self.pos + 1 && self.eat_register("A") {
+            return Ok(Operand::Accumulator);
+        }
+        if self.eat("#") {
+            return Ok(Operand::Immediate(self.expression()?));
+        }
+        if self.peek().is_some_and(|token| token.is_punct("(")) {
+            let start = self.pos;
+            if let Some(operand) = self.indirect()? {
+                return Ok(operand);
+            }
+            // Just an expression in parentheses
+            self.pos = start;
+        }
+        let address = self.expression()?;
+        if !self.eat(",") {
+            return Ok(Operand::Direct(address));
+        }
+        if self.eat_register("X") {
+            Ok(Operand::IndexedX(address))
+        } else if self.eat_register("Y") {
+            Ok(Operand::IndexedY(address))
+        } else {
+            Ok(Operand::Pair(address, self.expression()?))
+        }
+    }
+
+    /// Parses `(address)`, `(address,X)` or `(address),Y`, or returns `None` if the operand is
+    /// something else starting with a parenthesis.
+    fn indirect(&mut self) -> Result<Option<Operand>, (ErrorKind, usize)> {
+        self.expect("(")?;
+        let address = self.expression()?;
+        if self.eat(",") {
+            if !self.eat_register("X") {
+                return self.error("\"X\"");
+            }
+            self.expect(")")?;
+            return Ok(Some(Operand::IndirectX(address)));
+        }
+        self.expect(")")?;
+        if self.at_end() {
+            return Ok(Some(Operand::Indirect(address)));
+        }
+        if self.peek().is_some_and(|token| token.is_punct(","))
+            && self
+                .peek_at(1)
+                .is_some_and(|token| matches!(&token.kind, TokenKind::Identifier(name) if name.eq_ignore_ascii_case("Y")))
+        {
+            self.pos += 2;
+            return Ok(Some(Operand::IndirectY(address)));
+        }
+        Ok(None)
+    }
+
+    /// Parses an expression. A leading `<` or `>` takes the low or high byte of all of it.
+    fn expression(&mut self) -> Result<Expr, (ErrorKind, usize)> {
+        if self.eat("<") {
+            return Ok(Expr::Unary(UnaryOp::LowByte, Box::new(self.binary(0)?)));
+        }
+        if self.eat(">") {
+            return Ok(Expr::Unary(UnaryOp::HighByte, Box::new(self.binary(0)?)));
+        }
+        self.binary(0)
+    }
+
+    /// Parses binary operators from the given precedence level up.
+    fn binary(&mut self, level: usize) -> Result<Expr, (ErrorKind, usize)> {
+        const LEVELS: &[&[(&str, BinaryOp)]] = &[
+            &[("|", BinaryOp::Or)],
+            &[("^", BinaryOp::Xor)],
+            &[("&", BinaryOp::And)],
+            &[("<<", BinaryOp::ShiftLeft), (">>", BinaryOp::ShiftRight)],
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Subtract)],
+            &[("*", BinaryOp::Multiply), ("/", BinaryOp::Divide)],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for &(punct, op) in LEVELS[level] {
+                let column = self.column();
+                if self.eat(punct) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary {
+                        op,
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                        column,
+                    };
+                    continue 'operators;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, (ErrorKind, usize)> {
+        if self.eat("-") {
+            return Ok(Expr::Unary(UnaryOp::Negate, Box::new(self.unary()?)));
+        }
+        if self.eat("~") {
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?)));
+        }
+        if self.eat("*") {
+            return Ok(Expr::CurrentAddress);
+        }
+        if self.eat("(") {
+            let inner = self.expression()?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        let Some(token) = self.peek() else {
+            return self.error("a value");
+        };
+        let expr = match &token.kind {
+            TokenKind::Number(value) => Expr::Number(*value),
+            TokenKind::Identifier(name) if !name.starts_with('.') => Expr::Symbol {
+                name: self.qualify(name, token.column)?,
+                column: token.column,
+            },
+            _ => return self.error("a value"),
+        };
+        self.pos += 1;
+        Ok(expr)
+    }
+}
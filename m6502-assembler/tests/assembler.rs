@@ -0,0 +1,210 @@
+//! Assembling small programs and checking the bytes, symbols and errors.
+
+use m6502::variant::Variant;
+use m6502_assembler::{assemble, Assembler, ErrorKind, Location, SymbolKind};
+
+fn bytes(source: &str) -> Vec<u8> {
+    assemble(source).unwrap().bytes
+}
+
+fn error(source: &str) -> (ErrorKind, usize, usize) {
+    let error = assemble(source).unwrap_err();
+    (error.kind, error.location.line, error.location.column)
+}
+
+#[test]
+fn addressing_modes() {
+    let source = "
+        .org $1000
+        lsr
+        rol a
+        lda #$10
+        lda $10
+        lda $1234
+        lda $10,x
+        ldx $10,y
+        lda $1234,y
+        jmp ($1234)
+        lda ($10,x)
+        lda ($10),y
+        lda ($10+$20)*2
+    ";
+    assert_eq!(
+        bytes(source),
+        [
+            0x4A, 0x2A, 0xA9, 0x10, 0xA5, 0x10, 0xAD, 0x34, 0x12, 0xB5, 0x10, 0xB6, 0x10, 0xB9,
+            0x34, 0x12, 0x6C, 0x34, 0x12, 0xA1, 0x10, 0xB1, 0x10, 0xA5, 0x60,
+        ]
+    );
+}
+
+#[test]
+fn forward_references_use_absolute_addressing() {
+    let program = assemble("lda later\nbne later\nlater: rts").unwrap();
+    assert_eq!(program.bytes, [0xAD, 0x05, 0x00, 0xD0, 0x00, 0x60]);
+    assert_eq!(program.symbols["later"].value, 5);
+    assert_eq!(program.symbols["later"].kind, SymbolKind::Label);
+}
+
+#[test]
+fn local_labels_belong_to_the_last_global_label() {
+    let source = "
+        .org $0600
+first:  ldx #3
+@loop:  dex
+        bne @loop
+second: ldy #3
+@loop:  dey
+        bne @loop
+        beq first
+    ";
+    let program = assemble(source).unwrap();
+    assert_eq!(
+        program.bytes,
+        [0xA2, 0x03, 0xCA, 0xD0, 0xFD, 0xA0, 0x03, 0x88, 0xD0, 0xFD, 0xF0, 0xF4]
+    );
+    assert_eq!(program.symbols["first@loop"].value, 0x0602);
+    assert_eq!(program.symbols["second@loop"].value, 0x0607);
+    assert_eq!(program.labels()[&0x0605], "second");
+}
+
+#[test]
+fn expressions_and_data() {
+    let source = "
+        .org $C000
+size = end - table
+table:
+        .byte <table, >table, size, -1, 'A', \"hi\"
+        .word table + 2 * 3, (1 << 12) | $0F ^ 3, ~0 & $FF
+end:
+    ";
+    assert_eq!(
+        bytes(source),
+        [0x00, 0xC0, 0x0D, 0xFF, 0x41, 0x68, 0x69, 0x06, 0xC0, 0x0C, 0x10, 0xFF, 0x00,][..0x0D]
+    );
+    assert_eq!(assemble(source).unwrap().symbols["size"].value, 13);
+}
+
+#[test]
+fn cmos_instructions() {
+    let source = "
+start:  stz $10
+        lda ($10)
+        jmp ($1234,x)
+        inc
+        smb3 $10
+        bbs3 $10,start
+        bra start
+    ";
+    let program = Assembler::with_variant(Variant::Cmos65C02)
+        .assemble(source)
+        .unwrap();
+    assert_eq!(
+        program.bytes,
+        [
+            0x64, 0x10, 0xB2, 0x10, 0x7C, 0x34, 0x12, 0x1A, 0xB7, 0x10, 0xBF, 0x10, 0xF3, 0x80,
+            0xF1,
+        ]
+    );
+    assert_eq!(error("stz $10").0, ErrorKind::UnknownMnemonic("STZ".into()));
+}
+
+#[test]
+fn includes_are_relative_to_the_including_file() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    let program = Assembler::new()
+        .assemble_file(format!("{dir}/main.asm"))
+        .unwrap();
+    assert_eq!(program.origin, 0x0800);
+    assert_eq!(
+        program.bytes,
+        [0xA9, 0x42, 0x8D, 0x00, 0x02, 0x4C, 0x02, 0x08]
+    );
+    assert_eq!(program.symbols["start@loop"].value, 0x0802);
+
+    let error = Assembler::new()
+        .assemble_file(format!("{dir}/cycle.asm"))
+        .unwrap_err();
+    assert_eq!(error.kind, ErrorKind::IncludeCycle("cycle.asm".into()));
+}
+
+#[test]
+fn org_can_go_back_to_free_space() {
+    let program = assemble("  .org $700\n  nop\n  .org $600\n  rts").unwrap();
+    assert_eq!(program.origin, 0x0600);
+    assert_eq!(program.bytes.len(), 0x101);
+    assert_eq!((program.bytes[0], program.bytes[0x100]), (0x60, 0xEA));
+}
+
+#[test]
+fn errors_point_at_the_culprit() {
+    assert_eq!(
+        error("  lda #1\n  lda ($10,y)"),
+        (
+            ErrorKind::Expected {
+                expected: "\"X\"".into(),
+                found: "\"y\"".into()
+            },
+            2,
+            12
+        )
+    );
+    assert_eq!(
+        error("  jmp nowhere"),
+        (ErrorKind::UndefinedSymbol("nowhere".into()), 1, 7)
+    );
+    assert_eq!(
+        error("  ldx $10,x"),
+        (
+            ErrorKind::InvalidAddressingMode {
+                mnemonic: "LDX".into(),
+                operand: "an address indexed by X"
+            },
+            1,
+            3
+        )
+    );
+    assert_eq!(
+        error("x: nop\nx: nop").0,
+        ErrorKind::DuplicateSymbol("x".into())
+    );
+    assert_eq!(
+        error("  lda #256").0,
+        ErrorKind::OutOfRange {
+            value: 256,
+            bits: 8
+        }
+    );
+    assert_eq!(
+        error("a: .org $200\n  .org later\nlater:").0,
+        ErrorKind::ForwardReference("later".into())
+    );
+    assert_eq!(error("  bne * + 200").0, ErrorKind::BranchOutOfRange(198));
+    assert_eq!(
+        error("  .org $FFFF\n  jmp $1234").0,
+        ErrorKind::AddressOverflow
+    );
+    assert_eq!(
+        error("  .org $600\n  nop\n  nop\n  .org $601\n  lda #1"),
+        (ErrorKind::Overwrite(0x0601), 5, 3)
+    );
+    assert_eq!(
+        error("@local: nop").0,
+        ErrorKind::LocalWithoutScope("@local".into())
+    );
+    assert_eq!(
+        error("  .byte \"caf\u{e9}\""),
+        (ErrorKind::NotAscii('\u{e9}'), 1, 3)
+    );
+
+    let error = assemble("\n  lda 1/0").unwrap_err();
+    assert_eq!(
+        error.location,
+        Location {
+            file: "<source>".into(),
+            line: 2,
+            column: 8
+        }
+    );
+    assert_eq!(error.to_string(), "<source>:2:8: Division by zero.");
+}
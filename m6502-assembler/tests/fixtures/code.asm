@@ -0,0 +1,3 @@
+@loop:
+  sta $0200
+  jmp @loop
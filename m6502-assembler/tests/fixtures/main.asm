@@ -0,0 +1,6 @@
+; Includes a file of constants and one of code.
+  .org $0800
+  .include "constants.asm"
+start:
+  lda #value
+  .include "code.asm"
@@ -0,0 +1,99 @@
+//! The emulator as it was before the opcode tables were built at compile time and
+//! [`run_cycles`](m6502::CPU::run_cycles) was added, to measure the speedup against. Only the
+//! `egui` drawing was taken out.
+
+use status::Status;
+use thiserror::Error;
+
+use self::{addressing_modes::OperandData, bus::Bus, instructions::Instruction};
+
+/// Addressing modes and how the CPU uses them
+pub mod addressing_modes;
+/// Memory bus
+pub mod bus;
+/// Main instruction logic
+pub mod execution;
+/// A list of instructions
+pub mod instructions;
+/// Opcode table generation and parsing
+pub mod opcode_table;
+/// Functions forking with stack.
+pub mod stack;
+/// Status register
+pub mod status;
+
+/// A 6502 CPU
+#[derive(Clone)]
+pub struct CPU<T: Bus> {
+    /// Program counter
+    pub pc: u16,
+    /// Accumulator
+    pub ac: u8,
+    /// Register X
+    pub x: u8,
+    /// Register Y
+    pub y: u8,
+    /// Status register
+    pub status: Status,
+    /// Stack pointer
+    ///
+    /// The stack is LIFO, top-down, 8 bit range, 0x0100 - 0x01FF.
+    pub stack_pointer: u8,
+    pub bus: T,
+    /// Cycles left until next command
+    pub cycles_left: u8,
+}
+
+impl<T> CPU<T>
+where
+    T: Bus,
+{
+    pub fn new(bus: T) -> Self {
+        Self {
+            pc: 0,
+            ac: 0,
+            x: 0,
+            y: 0,
+            status: Status::default(),
+            stack_pointer: 0,
+            bus,
+            cycles_left: 0,
+        }
+    }
+}
+impl<T: Bus> CPU<T> {
+    pub fn fetch_byte(&mut self) -> u8 {
+        let r = self.bus.read(self.pc as usize);
+        self.pc += 1;
+        r
+    }
+
+    pub fn fetch_word(&mut self) -> u16 {
+        let r = self.bus.read_word(self.pc as usize);
+        self.pc += 2;
+        r
+    }
+
+    pub fn tick(&mut self) -> Result<(), CPUError> {
+        if self.cycles_left == 0 {
+            self.execute()?;
+        }
+        self.cycles_left -= 1;
+        self.bus.tick();
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CPUError {
+    #[error("No instruction implemented for opcode {0:#04X}.")]
+    NoInstruction(u8),
+    #[error("Instruction {0:?} unimplemented.")]
+    UnimplementedInstruction(Instruction),
+    #[error("Operand {0:?} has no data.")]
+    OperandNoData(OperandData),
+    #[error("Operand {0:?} is not an address.")]
+    OperandNotAddress(OperandData),
+    #[error("Operand {0:?} can't be written to.")]
+    OperandNotWriteable(OperandData),
+}
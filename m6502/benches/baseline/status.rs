@@ -0,0 +1,44 @@
+macro_rules! flag {
+    ($f:ident, $set:ident, $bit:expr) => {
+        impl Status {
+            pub fn $f(&self) -> bool {
+                (self.byte & (1 << $bit)) != 0
+            }
+
+            pub fn $set(&mut self, value: bool) {
+                self.set_mask(value, 1 << $bit)
+            }
+        }
+    };
+}
+
+flag!(carry, set_carry, 0);
+flag!(zero, set_zero, 1);
+flag!(interrupt_disabled, set_interrupt_disabled, 2);
+flag!(decimal, set_decimal, 3);
+flag!(break_flag, set_break, 4);
+flag!(_ignored_flag, _set_ignored_flag, 5);
+flag!(overflow, set_overflow, 6);
+flag!(negative, set_negative, 7);
+
+#[derive(Clone)]
+pub struct Status {
+    pub byte: u8,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Self { byte: 1 << 5 }
+    }
+}
+
+impl Status {
+    #[inline]
+    fn set_mask(&mut self, value: bool, mask: u8) {
+        if value {
+            self.byte |= mask
+        } else {
+            self.byte &= !mask
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Emulated MHz of the ways to run the CPU, on Klaus Dormann's functional test and on a small loop,
+//! and of the emulator this crate started from on the same loop.
+//!
+//! Run with `cargo bench -p m6502`.
+
+#[allow(dead_code, clippy::all)]
+mod baseline;
+
+use std::time::{Duration, Instant};
+
+use m6502::{bus::Ram, CPU};
+
+/// Cycles run per measurement, a bit more than a quarter of the functional test
+const CYCLES: u64 = 30_000_000;
+
+/// Sums a table into an accumulator and calls a subroutine that flips a byte through a pointer,
+/// forever. Unlike the functional test, the [`baseline`] emulator runs it correctly, so it can be
+/// compared against that.
+const LOOP: &[(u16, &[u8])] = &[
+    (
+        0x0600,
+        &[
+            0xA2, 0x00, // LDX #$00
+            0xA0, 0x00, // LDY #$00
+            0xBD, 0x00, 0x03, // LDA $0300,X
+            0x18, // CLC
+            0x65, 0x10, // ADC $10
+            0x99, 0x00, 0x04, // STA $0400,Y
+            0x85, 0x10, // STA $10
+            0x20, 0x20, 0x06, // JSR $0620
+            0xE8, // INX
+            0xC8, // INY
+            0xD0, 0xEE, // BNE $0604
+            0x4C, 0x00, 0x06, // JMP $0600
+        ],
+    ),
+    (
+        0x0620,
+        &[
+            0x48, // PHA
+            0xB1, 0x20, // LDA ($20),Y
+            0x49, 0x55, // EOR #$55
+            0x91, 0x20, // STA ($20),Y
+            0x68, // PLA
+            0x60, // RTS
+        ],
+    ),
+    (0x0020, &[0x00, 0x05]),
+];
+
+fn functional_test() -> CPU<Ram> {
+    let mut ram = Ram::new();
+    ram.load(
+        include_bytes!("../tests/fixtures/6502_functional_test.bin"),
+        0,
+    );
+    let mut cpu = CPU::new(ram);
+    cpu.pc = 0x0400;
+    cpu
+}
+
+fn small_loop() -> CPU<Ram> {
+    let mut ram = Ram::new();
+    for (address, bytes) in LOOP {
+        ram.load(bytes, (*address).into());
+    }
+    let mut cpu = CPU::new(ram);
+    cpu.pc = 0x0600;
+    cpu.stack_pointer = 0xFF;
+    cpu
+}
+
+fn baseline_loop() -> baseline::CPU<baseline::bus::Ram> {
+    let mut ram = baseline::bus::Ram::new();
+    for (address, bytes) in LOOP {
+        ram.load(bytes, (*address).into());
+    }
+    let mut cpu = baseline::CPU::new(ram);
+    cpu.pc = 0x0600;
+    cpu.stack_pointer = 0xFF;
+    cpu
+}
+
+/// Emulated MHz of `run` on the CPU `setup` makes
+fn measure<C>(name: &str, setup: fn() -> C, run: fn(&mut C)) -> f64 {
+    // The best of a few runs, the least disturbed by whatever else the machine is doing
+    let mut best = Duration::MAX;
+    for _ in 0..4 {
+        let mut cpu = setup();
+        let start = Instant::now();
+        run(&mut cpu);
+        best = best.min(start.elapsed());
+    }
+    let mhz = CYCLES as f64 / best.as_secs_f64() / 1e6;
+    println!(
+        "{name:<26} {:>8.1} ms {mhz:>10.1} MHz",
+        best.as_secs_f64() * 1e3
+    );
+    mhz
+}
+
+fn tick(cpu: &mut CPU<Ram>) {
+    for _ in 0..CYCLES {
+        cpu.tick().unwrap();
+    }
+}
+
+fn baseline_tick(cpu: &mut baseline::CPU<baseline::bus::Ram>) {
+    for _ in 0..CYCLES {
+        cpu.tick().unwrap();
+    }
+}
+
+fn run_cycles(cpu: &mut CPU<Ram>) {
+    cpu.run_cycles(CYCLES).unwrap();
+}
+
+fn execute(cpu: &mut CPU<Ram>) {
+    while cpu.total_cycles < CYCLES {
+        cpu.execute().unwrap();
+    }
+}
+
+fn main() {
+    measure("functional test tick", functional_test, tick);
+    let functional_run_cycles = measure("functional test run_cycles", functional_test, run_cycles);
+    let functional_execute = measure("functional test execute", functional_test, execute);
+    let loop_baseline = measure("loop baseline tick", baseline_loop, baseline_tick);
+    measure("loop tick", small_loop, tick);
+    let loop_run_cycles = measure("loop run_cycles", small_loop, run_cycles);
+    let loop_execute = measure("loop execute", small_loop, execute);
+    println!(
+        "run_cycles is {:.1}x as fast as execute on the functional test, {:.1}x on the loop",
+        functional_run_cycles / functional_execute,
+        loop_run_cycles / loop_execute
+    );
+    println!(
+        "run_cycles is {:.1}x as fast as ticking the baseline through the loop",
+        loop_run_cycles / loop_baseline
+    );
+}
@@ -1,10 +1,10 @@
-use super::{bus::Bus, CPUError, CPU};
+use super::{bus::Bus, instructions::Instruction, ErrorKind, CPU};
 
 /// Addressing Mode
 ///
 /// Each instruction has an addressing mode. Depending on it, we can (or can't) read/write value or
 /// get an address.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum AddressingMode {
     /// Operation on a register
     ///
@@ -83,6 +83,8 @@ pub enum AddressingMode {
     /// Mnemonic examples:
     /// ```text
     /// JMP ($FF82) (jump to address given in addresses "$FF82" and "$FF83")
+    /// JMP ($30FF) (jump to address given in addresses "$30FF" and "$3000" on the NMOS chip,
+    ///     which doesn't carry into the high byte of the pointer address)
     /// ```
     Indirect,
     /// An 8-bit zero-page address is provided after the instruction. X is added to it,
@@ -122,6 +124,140 @@ pub enum AddressingMode {
     /// BCC $084A (branch to location "$084A", if the carry flag is clear.)
     /// ```
     Relative,
+    /// An 8-bit zero-page address is provided after the instruction. A 16-bit value is read
+    /// from memory at this address then used for addressing. 65C02 only.
+    ///
+    /// Mnemonic examples:
+    /// ```text
+    /// LDA ($70) (load the contents of the location given in addresses "$0070" and "$0071"
+    ///     into A)
+    /// ```
+    ZeroPageIndirect,
+    /// A 16-bit address is provided after the instruction. X is added to it, then a 16-bit
+    /// value is read from memory at the result and used for addressing. 65C02 only.
+    ///
+    /// Mnemonic examples:
+    /// ```text
+    /// JMP ($2000,X) (jump to address given in addresses "$2000+X" and "$2001+X")
+    /// ```
+    AbsoluteIndirectX,
+    /// An 8-bit zero-page address and a signed 8-bit value are provided after the instruction.
+    /// The contents of the zero-page address are tested, and the resulting address is the
+    /// signed value added to the next PC. Rockwell 65C02 only.
+    ///
+    /// Mnemonic examples:
+    /// ```text
+    /// BBR0 $12,$1005 (branch to location "$1005", if bit 0 of address "$0012" is clear.)
+    /// ```
+    ZeroPageRelative,
+}
+
+/// How an instruction uses the memory its operand points to.
+///
+/// Indexed addressing modes make an extra read from the not-yet-fixed address whenever the
+/// page is crossed, but instructions that write have to wait for the fixed address and make
+/// that read every time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// The value is read (or the operand isn't memory at all)
+    Read,
+    /// A value is written without reading the old one
+    Write,
+    /// The value is read, modified and written back
+    ReadModifyWrite,
+    /// Registers are pushed to or pulled from the stack.
+    Stack,
+    /// The PC moves to the target if a condition holds. BBR and BBS also read their zero
+    /// page operand.
+    Branch,
+    /// The PC moves unconditionally: jumps, subroutine calls and returns, and BRK.
+    Jump,
+}
+
+impl Access {
+    /// The access class of an instruction
+    pub const fn of(instruction: Instruction) -> Self {
+        match instruction {
+            Instruction::JMP
+            | Instruction::JSR
+            | Instruction::RTS
+            | Instruction::RTI
+            | Instruction::BRK => Access::Jump,
+            Instruction::BCC
+            | Instruction::BCS
+            | Instruction::BEQ
+            | Instruction::BMI
+            | Instruction::BNE
+            | Instruction::BPL
+            | Instruction::BVC
+            | Instruction::BVS
+            | Instruction::BRA
+            | Instruction::BBR0
+            | Instruction::BBR1
+            | Instruction::BBR2
+            | Instruction::BBR3
+            | Instruction::BBR4
+            | Instruction::BBR5
+            | Instruction::BBR6
+            | Instruction::BBR7
+            | Instruction::BBS0
+            | Instruction::BBS1
+            | Instruction::BBS2
+            | Instruction::BBS3
+            | Instruction::BBS4
+            | Instruction::BBS5
+            | Instruction::BBS6
+            | Instruction::BBS7 => Access::Branch,
+            Instruction::PHA
+            | Instruction::PHP
+            | Instruction::PLA
+            | Instruction::PLP
+            | Instruction::PHX
+            | Instruction::PHY
+            | Instruction::PLX
+            | Instruction::PLY => Access::Stack,
+            Instruction::STA
+            | Instruction::STX
+            | Instruction::STY
+            | Instruction::SAX
+            | Instruction::SHA
+            | Instruction::SHX
+            | Instruction::SHY
+            | Instruction::TAS
+            | Instruction::STZ => Access::Write,
+            Instruction::ASL
+            | Instruction::LSR
+            | Instruction::ROL
+            | Instruction::ROR
+            | Instruction::INC
+            | Instruction::DEC
+            | Instruction::SLO
+            | Instruction::RLA
+            | Instruction::SRE
+            | Instruction::RRA
+            | Instruction::DCP
+            | Instruction::ISC
+            | Instruction::TSB
+            | Instruction::TRB
+            | Instruction::RMB0
+            | Instruction::RMB1
+            | Instruction::RMB2
+            | Instruction::RMB3
+            | Instruction::RMB4
+            | Instruction::RMB5
+            | Instruction::RMB6
+            | Instruction::RMB7
+            | Instruction::SMB0
+            | Instruction::SMB1
+            | Instruction::SMB2
+            | Instruction::SMB3
+            | Instruction::SMB4
+            | Instruction::SMB5
+            | Instruction::SMB6
+            | Instruction::SMB7 => Access::ReadModifyWrite,
+            _ => Access::Read,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -129,79 +265,205 @@ pub enum OperandData {
     Implied,
     Literal(u8),
     Address(u16),
+    /// The contents of a zero-page address, already read, and a branch target
+    ZeroPageRelative {
+        value: u8,
+        target: u16,
+    },
 }
 
 impl OperandData {
-    pub fn value(self, cpu: &CPU<impl Bus>) -> Result<u8, CPUError> {
+    pub fn value(self, cpu: &mut CPU<impl Bus>) -> Result<u8, ErrorKind> {
         match self {
             OperandData::Implied => Ok(cpu.ac),
             OperandData::Literal(lit) => Ok(lit),
-            OperandData::Address(addr) => Ok(cpu.bus.read(addr as usize)),
+            OperandData::Address(addr) => Ok(cpu.read_operand(addr)),
+            OperandData::ZeroPageRelative { value, .. } => Ok(value),
         }
     }
 
-    pub fn write(self, cpu: &mut CPU<impl Bus>, value: u8) -> Result<(), CPUError> {
+    pub fn write(self, cpu: &mut CPU<impl Bus>, value: u8) -> Result<(), ErrorKind> {
         match self {
             OperandData::Implied => {
                 cpu.ac = value;
                 Ok(())
             }
-            OperandData::Literal(_) => Err(CPUError::OperandNotWriteable(self)),
+            OperandData::Literal(_) | OperandData::ZeroPageRelative { .. } => {
+                Err(ErrorKind::OperandNotWriteable(self))
+            }
             OperandData::Address(addr) => {
-                cpu.bus.write(addr as usize, value);
+                cpu.write_operand(addr, value);
                 Ok(())
             }
         }
     }
 
-    pub fn address(self) -> Result<u16, CPUError> {
+    /// Writes back the result of a read-modify-write instruction.
+    ///
+    /// Like the real chip, memory operands get the unmodified value written first. The 65C02
+    /// reads them a second time instead.
+    pub fn write_modified(
+        self,
+        cpu: &mut CPU<impl Bus>,
+        old: u8,
+        new: u8,
+    ) -> Result<(), ErrorKind> {
+        if let OperandData::Address(addr) = self {
+            if cpu.variant.is_cmos() {
+                cpu.read_bus(addr);
+            } else {
+                cpu.write_bus(addr, old);
+            }
+        }
+        self.write(cpu, new)
+    }
+
+    pub fn address(self) -> Result<u16, ErrorKind> {
         match self {
             OperandData::Address(addr) => Ok(addr),
-            _ => Err(CPUError::OperandNotAddress(self)),
+            OperandData::ZeroPageRelative { target, .. } => Ok(target),
+            _ => Err(ErrorKind::OperandNotAddress(self)),
         }
     }
 }
 
+/// Whether two addresses lie on different 256-byte pages
+pub fn crosses_page(from: u16, to: u16) -> bool {
+    (from & 0xFF00) != (to & 0xFF00)
+}
+
 impl<T: Bus> CPU<T> {
-    pub fn fetch_op_data(&mut self, mode: AddressingMode) -> OperandData {
+    /// Fetches the operand of an instruction with the given addressing mode, making the same
+    /// bus accesses as the real chip (including the dummy ones) up to the operand itself.
+    ///
+    /// Also returns whether the effective address is on a different page than the base address
+    /// (for indexed modes) or the next instruction (for relative mode).
+    #[inline(always)]
+    pub fn fetch_op_data(&mut self, mode: AddressingMode, access: Access) -> (OperandData, bool) {
         match mode {
-            AddressingMode::Implied => OperandData::Implied,
-            AddressingMode::Immediate => OperandData::Literal(self.fetch_byte()),
-            AddressingMode::Absolute => OperandData::Address(self.fetch_word()),
-            AddressingMode::ZeroPage => OperandData::Address(self.fetch_byte() as u16),
-            AddressingMode::AbsoluteX => OperandData::Address(self.fetch_word() + self.x as u16),
-            AddressingMode::AbsoluteY => OperandData::Address(self.fetch_word() + self.y as u16),
+            AddressingMode::Implied => {
+                // The byte after the opcode is read and discarded.
+                self.read_bus(self.pc);
+                (OperandData::Implied, false)
+            }
+            AddressingMode::Immediate => (OperandData::Literal(self.fetch_byte()), false),
+            AddressingMode::Absolute => (OperandData::Address(self.fetch_word()), false),
+            AddressingMode::ZeroPage => (OperandData::Address(self.fetch_byte() as u16), false),
+            AddressingMode::AbsoluteX => {
+                let base = self.fetch_word();
+                self.index(base, self.x, access)
+            }
+            AddressingMode::AbsoluteY => {
+                let base = self.fetch_word();
+                self.index(base, self.y, access)
+            }
             AddressingMode::ZeroPageX => {
-                OperandData::Address(self.fetch_byte() as u16 + self.x as u16)
+                let base = self.fetch_byte();
+                // The base address is read while X is being added. The sum stays in page zero.
+                self.read_bus(base as u16);
+                (
+                    OperandData::Address(base.wrapping_add(self.x) as u16),
+                    false,
+                )
             }
             AddressingMode::ZeroPageY => {
-                OperandData::Address(self.fetch_byte() as u16 + self.y as u16)
+                let base = self.fetch_byte();
+                self.read_bus(base as u16);
+                (
+                    OperandData::Address(base.wrapping_add(self.y) as u16),
+                    false,
+                )
             }
             AddressingMode::Indirect => {
                 let addr = self.fetch_word();
-                let target = self.bus.read_word(addr as usize);
-                OperandData::Address(target)
+                let target = if self.variant.is_cmos() {
+                    // The 65C02 spends an extra cycle reading the last operand byte again.
+                    self.read_bus(self.pc.wrapping_sub(1));
+                    self.read_pointer(addr)
+                } else {
+                    // The NMOS chip doesn't carry into the high byte of the pointer address,
+                    // so JMP ($xxFF) reads the high byte of the target from $xx00.
+                    let low = self.read_data(addr) as u16;
+                    let high = self.read_data((addr & 0xFF00) | (addr.wrapping_add(1) & 0x00FF));
+                    low | ((high as u16) << 8)
+                };
+                (OperandData::Address(target), false)
             }
             AddressingMode::IndirectX => {
-                let addr = self.fetch_byte() as u16 + self.x as u16;
-                let target = self.bus.read_word(addr as usize);
-                OperandData::Address(target)
+                let base = self.fetch_byte();
+                self.read_bus(base as u16);
+                let target = self.read_zero_page_pointer(base.wrapping_add(self.x));
+                (OperandData::Address(target), false)
             }
             AddressingMode::IndirectY => {
-                let addr = self.fetch_byte() as u16;
-                let target = self.bus.read_word(addr as usize) + self.y as u16;
-                OperandData::Address(target)
+                let addr = self.fetch_byte();
+                let base = self.read_zero_page_pointer(addr);
+                self.index(base, self.y, access)
             }
             AddressingMode::Relative => {
-                let obyte = self.fetch_byte();
-                let offset = i8::from_be_bytes([obyte]);
-                // log::info!("Relative addr byte: 0b{obyte:08b} offset: {offset}");
-                if offset >= 0 {
-                    OperandData::Address(self.pc + (offset as u16))
-                } else {
-                    OperandData::Address(self.pc - (-offset as u16))
-                }
+                let target = self.fetch_relative();
+                (OperandData::Address(target), crosses_page(self.pc, target))
+            }
+            AddressingMode::ZeroPageIndirect => {
+                let addr = self.fetch_byte();
+                let target = self.read_zero_page_pointer(addr);
+                (OperandData::Address(target), false)
+            }
+            AddressingMode::AbsoluteIndirectX => {
+                let base = self.fetch_word();
+                self.read_bus(self.pc.wrapping_sub(1));
+                let target = self.read_pointer(base.wrapping_add(self.x as u16));
+                (OperandData::Address(target), false)
+            }
+            AddressingMode::ZeroPageRelative => {
+                let addr = self.fetch_byte() as u16;
+                let value = self.read_data(addr);
+                // The value is read a second time while it's being tested.
+                self.read_bus(addr);
+                let target = self.fetch_relative();
+                let operand = OperandData::ZeroPageRelative { value, target };
+                (operand, crosses_page(self.pc, target))
+            }
+        }
+    }
+
+    /// Adds an index register to a base address.
+    ///
+    /// The chip adds the index to the low byte first and reads from the resulting address while
+    /// it fixes the high byte. That read is only wasted if the page was crossed, or if the
+    /// instruction doesn't read its operand straight away. The 65C02 reads the last operand
+    /// byte again instead.
+    #[inline(always)]
+    fn index(&mut self, base: u16, index: u8, access: Access) -> (OperandData, bool) {
+        let target = base.wrapping_add(index as u16);
+        let crossed = crosses_page(base, target);
+        if crossed || matches!(access, Access::Write | Access::ReadModifyWrite) {
+            if self.variant.is_cmos() {
+                self.read_bus(self.pc.wrapping_sub(1));
+            } else {
+                self.read_bus((base & 0xFF00) | (target & 0x00FF));
             }
         }
+        (OperandData::Address(target), crossed)
+    }
+
+    /// Fetches a signed offset and returns it added to the next PC.
+    fn fetch_relative(&mut self) -> u16 {
+        let offset = i8::from_be_bytes([self.fetch_byte()]);
+        self.pc.wrapping_add_signed(offset as i16)
+    }
+
+    /// Reads a 16-bit pointer, low byte first, wrapping around at the end of memory.
+    fn read_pointer(&mut self, addr: u16) -> u16 {
+        let low = self.read_data(addr) as u16;
+        let high = self.read_data(addr.wrapping_add(1)) as u16;
+        low | (high << 8)
+    }
+
+    /// Reads a 16-bit pointer from page zero. A pointer at $FF has its high byte at $00.
+    fn read_zero_page_pointer(&mut self, addr: u8) -> u16 {
+        let low = self.read_data(addr as u16) as u16;
+        let high = self.read_data(addr.wrapping_add(1) as u16) as u16;
+        low | (high << 8)
     }
 }
@@ -0,0 +1,179 @@
+use alloc::vec::Vec;
+use core::{fmt, ops::RangeInclusive};
+
+use super::{bus::Bus, CPU};
+
+/// What a breakpoint watches
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BreakpointKind {
+    /// Stops before the instruction at the address runs.
+    Execute,
+    /// Stops after an instruction reads the address. Fetching the opcode and operand doesn't
+    /// count, but the dummy reads the chip makes along the way do.
+    Read,
+    /// Stops after an instruction writes the address.
+    Write,
+}
+
+/// A breakpoint on an address or a range of addresses
+#[derive(Clone, Debug)]
+pub struct Breakpoint {
+    pub kind: BreakpointKind,
+    pub range: RangeInclusive<u16>,
+    /// Disabled breakpoints are neither counted nor stopped at.
+    pub enabled: bool,
+    /// How many times the breakpoint was hit
+    pub hits: u64,
+}
+
+/// Identifies a breakpoint in [`Breakpoints`]
+pub type BreakpointId = usize;
+
+/// Why execution stopped
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Break {
+    pub id: BreakpointId,
+    pub kind: BreakpointKind,
+    /// The address that was executed, read or written
+    pub address: u16,
+}
+
+impl fmt::Display for Break {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = match self.kind {
+            BreakpointKind::Execute => "execution",
+            BreakpointKind::Read => "read",
+            BreakpointKind::Write => "write",
+        };
+        write!(
+            f,
+            "Breakpoint {} hit by {access} of ${:04X}",
+            self.id, self.address
+        )
+    }
+}
+
+/// The breakpoints of a CPU, and the stop they caused
+#[derive(Clone, Debug, Default)]
+pub struct Breakpoints {
+    /// Removed breakpoints leave a hole, so that ids stay valid.
+    list: Vec<Option<Breakpoint>>,
+    /// Whether any breakpoint of each kind is enabled, to skip the checks otherwise
+    watching: [bool; 3],
+    /// The first breakpoint hit by the instruction or cycle in progress
+    hit: Option<Break>,
+    /// An execute breakpoint was reported at this address, and the next instruction there runs
+    /// without stopping again.
+    resume_at: Option<u16>,
+}
+
+impl Breakpoints {
+    /// Adds an enabled breakpoint.
+    pub fn add(&mut self, kind: BreakpointKind, range: RangeInclusive<u16>) -> BreakpointId {
+        self.list.push(Some(Breakpoint {
+            kind,
+            range,
+            enabled: true,
+            hits: 0,
+        }));
+        self.update();
+        self.list.len() - 1
+    }
+
+    pub fn remove(&mut self, id: BreakpointId) -> Option<Breakpoint> {
+        let removed = self.list.get_mut(id)?.take();
+        self.update();
+        removed
+    }
+
+    pub fn get(&self, id: BreakpointId) -> Option<&Breakpoint> {
+        self.list.get(id)?.as_ref()
+    }
+
+    /// Enables or disables a breakpoint. Does nothing if there is no such breakpoint.
+    pub fn set_enabled(&mut self, id: BreakpointId, enabled: bool) {
+        if let Some(Some(breakpoint)) = self.list.get_mut(id) {
+            breakpoint.enabled = enabled;
+            self.update();
+        }
+    }
+
+    /// The breakpoints with their ids
+    pub fn iter(&self) -> impl Iterator<Item = (BreakpointId, &Breakpoint)> {
+        self.list
+            .iter()
+            .enumerate()
+            .filter_map(|(id, breakpoint)| Some((id, breakpoint.as_ref()?)))
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+        self.update();
+    }
+
+    fn update(&mut self) {
+        self.watching = [false; 3];
+        for breakpoint in self.list.iter().flatten().filter(|b| b.enabled) {
+            self.watching[breakpoint.kind as usize] = true;
+        }
+    }
+
+    /// Counts a hit on every enabled breakpoint of `kind` covering `address`, and remembers the
+    /// first one as the reason to stop.
+    #[inline]
+    fn check(&mut self, kind: BreakpointKind, address: u16) {
+        if self.watching[kind as usize] {
+            self.check_list(kind, address);
+        }
+    }
+
+    fn check_list(&mut self, kind: BreakpointKind, address: u16) {
+        for (id, slot) in self.list.iter_mut().enumerate() {
+            let Some(breakpoint) = slot else {
+                continue;
+            };
+            if breakpoint.enabled && breakpoint.kind == kind && breakpoint.range.contains(&address)
+            {
+                breakpoint.hits += 1;
+                self.hit.get_or_insert(Break { id, kind, address });
+            }
+        }
+    }
+
+    /// Whether any breakpoint is enabled, or the CPU is resuming from one
+    pub(crate) fn is_active(&self) -> bool {
+        self.watching.contains(&true) || self.resume_at.is_some()
+    }
+
+    /// Whether an execute breakpoint stopped the CPU before an instruction
+    pub(crate) fn stopped_before_instruction(&self) -> bool {
+        self.hit
+            .is_some_and(|hit| hit.kind == BreakpointKind::Execute)
+    }
+
+    pub(crate) fn take_hit(&mut self) -> Option<Break> {
+        self.hit.take()
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    /// Checks the execute breakpoints before running the instruction at PC. Returns `true` if
+    /// the CPU should stop instead.
+    pub(crate) fn break_before_instruction(&mut self) -> bool {
+        if self.breakpoints.resume_at.take() == Some(self.pc) {
+            return false;
+        }
+        self.breakpoints.check(BreakpointKind::Execute, self.pc);
+        if self.breakpoints.stopped_before_instruction() {
+            self.breakpoints.resume_at = Some(self.pc);
+            return true;
+        }
+        false
+    }
+
+    /// Checks the read and write breakpoints on an access made by an instruction.
+    #[inline]
+    pub(crate) fn watch(&mut self, kind: BreakpointKind, address: u16) {
+        self.breakpoints.check(kind, address);
+    }
+}
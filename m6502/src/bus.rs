@@ -1,31 +1,99 @@
+use alloc::{vec, vec::Vec};
+
+use super::snapshot::SnapshotError;
+
+pub use memory_map::{Device, MapError, MemoryMap, MemoryMapBuilder, Unmapped, UnmappedAccess};
+
+/// Building a bus out of RAM, ROM, mirrors and devices
+mod memory_map;
+
 /// Memory bus. Represents all the memory-mapped devices.
+///
+/// Addresses no device answers to are open bus: [`read`](Bus::read) and [`peek`](Bus::peek)
+/// return `None` and the CPU sees whatever was last on the data bus.
 pub trait Bus {
-    fn read(&self, addr: usize) -> u8;
-    fn write(&mut self, addr: usize, data: u8);
+    /// Reads a byte, triggering whatever the device does on read (clearing status flags,
+    /// shifting out controller bits, acknowledging interrupts...).
+    fn read(&mut self, addr: u16) -> Option<u8>;
+    /// Reads a byte without any side effects, for debuggers and disassemblers. Returns `None`
+    /// if nothing is mapped there, or if the device can't be read without side effects.
+    fn peek(&self, addr: u16) -> Option<u8>;
+    fn write(&mut self, addr: u16, data: u8);
+
+    fn read_word(&mut self, addr: u16) -> Option<u16> {
+        let low = self.read(addr)? as u16;
+        let high = self.read(addr.wrapping_add(1))? as u16;
+        Some(low | (high << 8))
+    }
 
-    fn read_word(&self, addr: usize) -> u16 {
-        self.read(addr) as u16 | ((self.read(addr + 1) as u16) << 8)
+    fn peek_word(&self, addr: u16) -> Option<u16> {
+        let low = self.peek(addr)? as u16;
+        let high = self.peek(addr.wrapping_add(1))? as u16;
+        Some(low | (high << 8))
     }
 
-    fn write_word(&mut self, addr: usize, data: u16) {
+    fn write_word(&mut self, addr: u16, data: u16) {
         self.write(addr, (data & 0xFF) as u8);
-        self.write(addr + 1, (data >> 8) as u8)
+        self.write(addr.wrapping_add(1), (data >> 8) as u8)
     }
 
     fn tick(&mut self) {}
+
+    /// Returns and clears the first access the bus refused since the last call. The CPU checks
+    /// after every instruction and interrupt sequence, and fails with
+    /// [`ErrorKind::Unmapped`](crate::ErrorKind::Unmapped) if there was one.
+    fn take_fault(&mut self) -> Option<UnmappedAccess> {
+        None
+    }
+
+    /// Where the byte at `addr` is in the ROM image, if it comes from ROM. Buses that switch
+    /// banks return the offset in the whole image, so that code/data logs tell banks apart.
+    fn rom_offset(&self, _addr: u16) -> Option<usize> {
+        None
+    }
+
+    /// Saves the contents of memory and the state of devices for a
+    /// [`Snapshot`](crate::snapshot::Snapshot), or returns `None` if the bus can't be saved.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restores a state returned by [`save_state`](Bus::save_state).
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), SnapshotError> {
+        Err(SnapshotError::Unsupported)
+    }
 }
 
+/// Plain memory starting at address 0. Addresses past its size are open bus.
 pub struct Ram {
     memory: Vec<u8>,
 }
 
 impl Bus for Ram {
-    fn read(&self, addr: usize) -> u8 {
-        self.memory[addr]
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.peek(addr)
     }
 
-    fn write(&mut self, addr: usize, data: u8) {
-        self.memory[addr] = data;
+    fn peek(&self, addr: u16) -> Option<u8> {
+        self.memory.get(addr as usize).copied()
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        if let Some(byte) = self.memory.get_mut(addr as usize) {
+            *byte = data;
+        }
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Some(self.memory.clone())
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError> {
+        if state.len() != self.memory.len() {
+            return Err(SnapshotError::Invalid("RAM size differs"));
+        }
+        self.memory.copy_from_slice(state);
+        Ok(())
     }
 }
 
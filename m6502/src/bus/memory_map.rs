@@ -0,0 +1,439 @@
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::{fmt, ops::RangeInclusive};
+
+use thiserror::Error;
+
+use super::Bus;
+use crate::snapshot::{Reader, SnapshotError};
+
+/// A memory-mapped device, such as a video chip, a sound chip or a controller port.
+///
+/// Addresses are offsets from the start of the range the device is mapped at.
+pub trait Device {
+    /// Name shown in the memory map listing
+    fn name(&self) -> &str {
+        "Device"
+    }
+    fn read(&mut self, offset: u16) -> Option<u8>;
+    /// Reads without side effects. By default nothing can be, so debuggers see open bus.
+    fn peek(&self, _offset: u16) -> Option<u8> {
+        None
+    }
+    fn write(&mut self, offset: u16, data: u8);
+    /// Called once per CPU cycle
+    fn tick(&mut self) {}
+    /// Where the byte at `offset` is in the device's ROM image, for cartridges and other
+    /// devices with banked ROM. See [`Bus::rom_offset`].
+    fn rom_offset(&self, _offset: u16) -> Option<usize> {
+        None
+    }
+
+    /// Saves the device's state for a snapshot, or returns `None` if it can't be saved.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restores a state returned by [`save_state`](Device::save_state).
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), SnapshotError> {
+        Err(SnapshotError::Unsupported)
+    }
+}
+
+/// What accessing an address with nothing mapped at it does
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Unmapped {
+    /// Reads see the last value on the data bus and writes are ignored.
+    #[default]
+    OpenBus,
+    /// Reads return 0 and writes are ignored.
+    Zero,
+    /// Like open bus, but the first such access is kept for [`Bus::take_fault`], failing the
+    /// instruction that made it.
+    Error,
+}
+
+/// An access to an address with nothing mapped at it, kept under [`Unmapped::Error`]
+#[derive(Error, Copy, Clone, Debug, PartialEq, Eq)]
+#[error("{} unmapped address {addr:#06X}.", if *write { "Write to" } else { "Read from" })]
+pub struct UnmappedAccess {
+    pub addr: u16,
+    pub write: bool,
+}
+
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum MapError {
+    #[error("{first} overlaps {second}.")]
+    Overlap { first: String, second: String },
+    #[error("Range ${start:04X}-${end:04X} is empty.")]
+    EmptyRange { start: u16, end: u16 },
+    #[error("{0} overlaps what it mirrors.")]
+    MirrorsItself(String),
+    #[error("{mirror} shows {other}, and mirrors of mirrors aren't followed.")]
+    MirrorOfMirror { mirror: String, other: String },
+}
+
+enum Contents {
+    Ram(Vec<u8>),
+    /// Repeated over the region if shorter than it
+    Rom(Vec<u8>),
+    /// Another range, repeated over the region
+    Mirror {
+        start: u16,
+        end: u16,
+    },
+    Device(Box<dyn Device>),
+}
+
+impl Contents {
+    /// Marks the state of RAM and devices in a snapshot, so that a snapshot of another map is
+    /// caught. ROM and mirrors have no state.
+    fn tag(&self) -> Option<u8> {
+        match self {
+            Contents::Ram(_) => Some(b'R'),
+            Contents::Device(_) => Some(b'D'),
+            Contents::Rom(_) | Contents::Mirror { .. } => None,
+        }
+    }
+}
+
+struct Region {
+    start: u16,
+    end: u16,
+    contents: Contents,
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${:04X}-${:04X} ", self.start, self.end)?;
+        match &self.contents {
+            Contents::Ram(memory) => write!(f, "RAM ({} bytes)", memory.len()),
+            Contents::Rom(data) => write!(f, "ROM ({} bytes)", data.len()),
+            Contents::Mirror { start, end } => write!(f, "mirror of ${start:04X}-${end:04X}"),
+            Contents::Device(device) => f.write_str(device.name()),
+        }
+    }
+}
+
+/// Collects the regions of a [`MemoryMap`].
+#[derive(Default)]
+pub struct MemoryMapBuilder {
+    regions: Vec<Region>,
+    unmapped: Unmapped,
+}
+
+impl MemoryMapBuilder {
+    fn region(mut self, range: RangeInclusive<u16>, contents: Contents) -> Self {
+        self.regions.push(Region {
+            start: *range.start(),
+            end: *range.end(),
+            contents,
+        });
+        self
+    }
+
+    /// Maps zero-filled RAM over the range.
+    pub fn ram(self, range: RangeInclusive<u16>) -> Self {
+        let size = range.clone().count();
+        self.region(range, Contents::Ram(vec![0; size]))
+    }
+
+    /// Maps read-only memory over the range, repeating `data` if it's shorter.
+    pub fn rom(self, range: RangeInclusive<u16>, data: impl Into<Vec<u8>>) -> Self {
+        self.region(range, Contents::Rom(data.into()))
+    }
+
+    /// Makes the range show whatever is mapped at `of`, repeating it if it's shorter. `of` can't
+    /// overlap the range or another mirror.
+    pub fn mirror(self, range: RangeInclusive<u16>, of: RangeInclusive<u16>) -> Self {
+        let contents = Contents::Mirror {
+            start: *of.start(),
+            end: *of.end(),
+        };
+        self.region(range, contents)
+    }
+
+    /// Maps a device over the range.
+    pub fn device(self, range: RangeInclusive<u16>, device: impl Device + 'static) -> Self {
+        self.region(range, Contents::Device(Box::new(device)))
+    }
+
+    /// Sets what accessing unmapped addresses does.
+    pub fn unmapped(mut self, unmapped: Unmapped) -> Self {
+        self.unmapped = unmapped;
+        self
+    }
+
+    /// Checks that no regions overlap and that mirrors show neither themselves nor other
+    /// mirrors, and builds the map.
+    pub fn build(mut self) -> Result<MemoryMap, MapError> {
+        for region in &self.regions {
+            let mut ranges = vec![(region.start, region.end)];
+            if let Contents::Mirror { start, end } = region.contents {
+                ranges.push((start, end));
+            }
+            for (start, end) in ranges {
+                if start > end {
+                    return Err(MapError::EmptyRange { start, end });
+                }
+            }
+        }
+        self.regions.sort_by_key(|region| region.start);
+        for pair in self.regions.windows(2) {
+            if pair[1].start <= pair[0].end {
+                return Err(MapError::Overlap {
+                    first: format!("{}", pair[0]),
+                    second: format!("{}", pair[1]),
+                });
+            }
+        }
+        for mirror in &self.regions {
+            let Contents::Mirror { start, end } = mirror.contents else {
+                continue;
+            };
+            for other in &self.regions {
+                if other.start > end || other.end < start {
+                    continue;
+                }
+                if core::ptr::eq(mirror, other) {
+                    return Err(MapError::MirrorsItself(format!("{mirror}")));
+                }
+                if let Contents::Mirror { .. } = other.contents {
+                    return Err(MapError::MirrorOfMirror {
+                        mirror: format!("{mirror}"),
+                        other: format!("{other}"),
+                    });
+                }
+            }
+        }
+        Ok(MemoryMap {
+            regions: self.regions,
+            unmapped: self.unmapped,
+            fault: None,
+        })
+    }
+}
+
+/// A bus made of RAM, ROM, mirrors and devices mapped at address ranges.
+///
+/// ```
+/// use m6502::bus::{Bus, MemoryMap};
+///
+/// let mut bus = MemoryMap::builder()
+///     .ram(0x0000..=0x07FF)
+///     .mirror(0x0800..=0x1FFF, 0x0000..=0x07FF)
+///     .rom(0x8000..=0xFFFF, [0xEA; 0x4000])
+///     .build()
+///     .unwrap();
+/// bus.write(0x0801, 0x42);
+/// assert_eq!(bus.read(0x0001), Some(0x42));
+/// assert_eq!(bus.read(0xC000), Some(0xEA));
+/// assert_eq!(bus.read(0x4000), None);
+/// ```
+pub struct MemoryMap {
+    /// Sorted by address, not overlapping
+    regions: Vec<Region>,
+    unmapped: Unmapped,
+    fault: Option<UnmappedAccess>,
+}
+
+impl MemoryMap {
+    pub fn builder() -> MemoryMapBuilder {
+        MemoryMapBuilder::default()
+    }
+
+    /// Index of the region `addr` is in
+    fn find(&self, addr: u16) -> Option<usize> {
+        let index = self.regions.partition_point(|region| region.end < addr);
+        (index < self.regions.len() && self.regions[index].start <= addr).then_some(index)
+    }
+
+    /// Finds the region `addr` is in, following mirrors, and the offset of `addr` in it.
+    fn resolve(&self, addr: u16) -> Option<(usize, u16)> {
+        let mut index = self.find(addr)?;
+        let mut addr = addr;
+        if let Contents::Mirror { start, end } = self.regions[index].contents {
+            let size = (end - start) as u32 + 1;
+            let offset = (addr - self.regions[index].start) as u32 % size;
+            addr = start + offset as u16;
+            index = self.find(addr)?;
+        }
+        Some((index, addr - self.regions[index].start))
+    }
+
+    fn unmapped(&mut self, addr: u16, write: bool) -> Option<u8> {
+        match self.unmapped {
+            Unmapped::OpenBus => None,
+            Unmapped::Zero => Some(0),
+            Unmapped::Error => {
+                self.fault.get_or_insert(UnmappedAccess { addr, write });
+                None
+            }
+        }
+    }
+
+    /// Copies `bytes` into the RAM and ROM mapped from `at` on, ROM included. Devices and
+    /// unmapped addresses are skipped.
+    pub fn load(&mut self, bytes: &[u8], at: u16) {
+        for (addr, &byte) in (at..=u16::MAX).zip(bytes) {
+            if let Some((index, offset)) = self.resolve(addr) {
+                match &mut self.regions[index].contents {
+                    Contents::Ram(memory) => memory[offset as usize] = byte,
+                    Contents::Rom(data) if !data.is_empty() => {
+                        let len = data.len();
+                        data[offset as usize % len] = byte;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl Bus for MemoryMap {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let Some((index, offset)) = self.resolve(addr) else {
+            return self.unmapped(addr, false);
+        };
+        match &mut self.regions[index].contents {
+            Contents::Device(device) => device.read(offset),
+            _ => self.peek(addr),
+        }
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        let Some((index, offset)) = self.resolve(addr) else {
+            return (self.unmapped == Unmapped::Zero).then_some(0);
+        };
+        match &self.regions[index].contents {
+            Contents::Ram(memory) => Some(memory[offset as usize]),
+            Contents::Rom(data) => data.get(offset as usize % data.len().max(1)).copied(),
+            Contents::Device(device) => device.peek(offset),
+            Contents::Mirror { .. } => None,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        let Some((index, offset)) = self.resolve(addr) else {
+            self.unmapped(addr, true);
+            return;
+        };
+        match &mut self.regions[index].contents {
+            Contents::Ram(memory) => memory[offset as usize] = data,
+            Contents::Device(device) => device.write(offset, data),
+            Contents::Rom(_) | Contents::Mirror { .. } => {}
+        }
+    }
+
+    fn tick(&mut self) {
+        for region in &mut self.regions {
+            if let Contents::Device(device) = &mut region.contents {
+                device.tick();
+            }
+        }
+    }
+
+    /// The first access to an unmapped address, if unmapped accesses are
+    /// [errors](Unmapped::Error)
+    fn take_fault(&mut self) -> Option<UnmappedAccess> {
+        self.fault.take()
+    }
+
+    /// ROM regions count as one image, laid out in address order. Devices report offsets in
+    /// their own image.
+    fn rom_offset(&self, addr: u16) -> Option<usize> {
+        let (index, offset) = self.resolve(addr)?;
+        match &self.regions[index].contents {
+            Contents::Rom(data) if !data.is_empty() => {
+                let before: usize = self.regions[..index]
+                    .iter()
+                    .map(|region| match &region.contents {
+                        Contents::Rom(data) => data.len(),
+                        _ => 0,
+                    })
+                    .sum();
+                Some(before + offset as usize % data.len())
+            }
+            Contents::Device(device) => device.rom_offset(offset),
+            _ => None,
+        }
+    }
+
+    /// Saves RAM and devices in address order, each state prefixed by the kind of region and
+    /// its length. ROM isn't saved.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        let mut state = Vec::new();
+        for region in &self.regions {
+            let device_state;
+            let region_state: &[u8] = match &region.contents {
+                Contents::Ram(memory) => memory,
+                Contents::Device(device) => {
+                    device_state = device.save_state()?;
+                    &device_state
+                }
+                Contents::Rom(_) | Contents::Mirror { .. } => continue,
+            };
+            state.extend(region.contents.tag());
+            state.extend((region_state.len() as u32).to_le_bytes());
+            state.extend(region_state);
+        }
+        Some(state)
+    }
+
+    /// Checks the kind and length of every region's state before loading any of them, then
+    /// loads the devices before the RAM, so that RAM is untouched if one rejects its state.
+    fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError> {
+        let differs = SnapshotError::Invalid("memory map differs");
+        let mut input = Reader::new(state);
+        let mut states = Vec::new();
+        for region in &self.regions {
+            let Some(tag) = region.contents.tag() else {
+                continue;
+            };
+            if input.u8()? != tag {
+                return Err(differs);
+            }
+            let len = input.u32()? as usize;
+            if matches!(&region.contents, Contents::Ram(memory) if memory.len() != len) {
+                return Err(differs);
+            }
+            states.push(input.bytes(len)?);
+        }
+        if !input.is_empty() {
+            return Err(differs);
+        }
+
+        let mut regions: Vec<_> = self
+            .regions
+            .iter_mut()
+            .filter(|region| region.contents.tag().is_some())
+            .zip(states)
+            .collect();
+        for (region, state) in &mut regions {
+            if let Contents::Device(device) = &mut region.contents {
+                device.load_state(state)?;
+            }
+        }
+        for (region, state) in regions {
+            if let Contents::Ram(memory) = &mut region.contents {
+                memory.copy_from_slice(state);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lists the regions, one per line, and what unmapped addresses do.
+impl fmt::Display for MemoryMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for region in &self.regions {
+            writeln!(f, "{region}")?;
+        }
+        write!(f, "Unmapped: {:?}", self.unmapped)
+    }
+}
+
+impl fmt::Debug for MemoryMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,224 @@
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path};
+
+use super::{
+    addressing_modes::AddressingMode, bus::Bus, instructions::Instruction,
+    opcode_table::OpcodeEntry, CPU,
+};
+
+/// The byte was executed, as an opcode or an operand.
+pub const CODE: u16 = 0x01;
+/// The byte was read as data by an instruction.
+pub const DATA: u16 = 0x02;
+/// Bits 13 and 14 of the address a ROM byte was seen at, in bits 2 and 3: which 8K bank of
+/// $8000-$FFFF it was mapped in
+pub const BANK: u16 = 0x0C;
+/// The byte is the first opcode after an indirect jump.
+pub const INDIRECT_CODE: u16 = 0x10;
+/// The byte was read or written through a pointer, with `(zp,X)`, `(zp),Y` or `(zp)`.
+pub const INDIRECT_DATA: u16 = 0x20;
+/// The byte is the first opcode of a subroutine called with JSR.
+pub const SUBROUTINE: u16 = 0x80;
+/// The byte was fetched as an opcode. Not part of the `.cdl` format.
+pub const OPCODE: u16 = 0x100;
+/// The byte was fetched as an operand. Not part of the `.cdl` format.
+pub const OPERAND: u16 = 0x200;
+/// The byte was written by an instruction. Not part of the `.cdl` format.
+pub const WRITTEN: u16 = 0x400;
+
+/// Marks what every byte the CPU touches is used for, while it runs.
+///
+/// Bytes that come from ROM, as reported by [`Bus::rom_offset`], are logged by their position
+/// in the ROM image, so that banks switched in at the same address are told apart. The others
+/// are logged by address.
+///
+/// The ROM log exports to the `.cdl` format of FCEUX and Mesen: one byte per byte of PRG ROM,
+/// with the low 8 bits of the flags above.
+#[derive(Clone)]
+pub struct CodeDataLogger {
+    /// Flags of every CPU address, for bytes not in ROM
+    addresses: Vec<u16>,
+    /// Flags of every byte of the ROM image
+    rom: Vec<u16>,
+    /// Whether the operand of the instruction in progress is accessed through a pointer
+    indirect: bool,
+    /// Flags for the next opcode fetched, left by jumps
+    next_opcode: u16,
+}
+
+impl CodeDataLogger {
+    /// Logs a ROM image of `rom_size` bytes, and everything else by address.
+    pub fn new(rom_size: usize) -> Self {
+        Self {
+            addresses: vec![0; 0x10000],
+            rom: vec![0; rom_size],
+            indirect: false,
+            next_opcode: 0,
+        }
+    }
+
+    /// Flags of the byte at a CPU address that isn't in ROM
+    pub fn address_flags(&self, addr: u16) -> u16 {
+        self.addresses[addr as usize]
+    }
+
+    /// Flags of a byte of the ROM image
+    pub fn rom_flags(&self, offset: usize) -> u16 {
+        self.rom.get(offset).copied().unwrap_or_default()
+    }
+
+    /// Exports the ROM log in the `.cdl` format.
+    pub fn to_cdl(&self) -> Vec<u8> {
+        self.rom.iter().map(|&flags| flags as u8).collect()
+    }
+
+    /// Exports the log of the whole address space in the `.cdl` layout, for programs run
+    /// without a ROM image.
+    pub fn addresses_to_cdl(&self) -> Vec<u8> {
+        self.addresses.iter().map(|&flags| flags as u8).collect()
+    }
+
+    /// Adds the flags of a `.cdl` file from an earlier session to the ROM log. Returns `false`
+    /// if it's not the size of the ROM image.
+    pub fn merge_cdl(&mut self, cdl: &[u8]) -> bool {
+        if cdl.len() != self.rom.len() {
+            return false;
+        }
+        for (flags, &byte) in self.rom.iter_mut().zip(cdl) {
+            *flags |= byte as u16;
+        }
+        true
+    }
+
+    /// Number of ROM bytes logged as code and as data
+    pub fn rom_coverage(&self) -> (usize, usize) {
+        let count = |flag| self.rom.iter().filter(|&&flags| flags & flag != 0).count();
+        (count(CODE), count(DATA))
+    }
+
+    pub fn clear(&mut self) {
+        self.addresses.fill(0);
+        self.rom.fill(0);
+    }
+
+    /// Notes how the instruction just decoded accesses memory.
+    #[cold]
+    fn note_instruction(&mut self, entry: &OpcodeEntry) {
+        self.indirect = matches!(
+            entry.addressing_mode,
+            AddressingMode::IndirectX
+                | AddressingMode::IndirectY
+                | AddressingMode::ZeroPageIndirect
+        );
+        self.next_opcode = match (entry.instruction, entry.addressing_mode) {
+            (Instruction::JSR, _) => SUBROUTINE,
+            (Instruction::JMP, AddressingMode::Indirect | AddressingMode::AbsoluteIndirectX) => {
+                INDIRECT_CODE
+            }
+            _ => 0,
+        };
+    }
+
+    #[cfg(feature = "std")]
+    pub fn save_cdl(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_cdl())
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    /// Adds flags to the byte at `addr`, if a code/data logger is attached.
+    #[inline]
+    pub(crate) fn log_code_data(&mut self, addr: u16, flags: u16) {
+        if self.hooked && self.code_data_logger.is_some() {
+            self.add_code_data_flags(addr, flags);
+        }
+    }
+
+    /// Kept out of the instructions, which only check whether a logger is attached
+    ///
+    /// When cycle-stepped, the accesses replayed were logged on their own cycle, and those
+    /// skipped haven't happened yet, so only the access of the current cycle is logged.
+    #[cold]
+    fn add_code_data_flags(&mut self, addr: u16, flags: u16) {
+        let Some(logger) = &mut self.code_data_logger else {
+            return;
+        };
+        if !self.replay.next_is_live() {
+            return;
+        }
+        match self.bus.rom_offset(addr) {
+            Some(offset) if offset < logger.rom.len() => {
+                logger.rom[offset] |= flags | ((addr & 0x6000) >> 11);
+            }
+            _ => logger.addresses[addr as usize] |= flags,
+        }
+    }
+
+    /// Logs an opcode fetch at `addr`.
+    #[inline]
+    pub(crate) fn log_opcode(&mut self, addr: u16) {
+        if self.hooked && self.code_data_logger.is_some() {
+            self.add_opcode_flags(addr);
+        }
+    }
+
+    /// Kept out of the instructions, like [`add_code_data_flags`](CPU::add_code_data_flags)
+    #[cold]
+    fn add_opcode_flags(&mut self, addr: u16) {
+        let Some(logger) = &mut self.code_data_logger else {
+            return;
+        };
+        // A replayed fetch mustn't take the flags the instruction left for the next opcode.
+        if !self.replay.next_is_live() {
+            return;
+        }
+        let flags = CODE | OPCODE | logger.next_opcode;
+        logger.next_opcode = 0;
+        logger.indirect = false;
+        self.log_code_data(addr, flags);
+    }
+
+    /// Notes how the instruction just decoded accesses memory, once its operand is fetched.
+    #[inline]
+    pub(crate) fn log_instruction(&mut self, entry: &OpcodeEntry) {
+        if !self.hooked {
+            return;
+        }
+        if let Some(logger) = &mut self.code_data_logger {
+            logger.note_instruction(entry);
+        }
+    }
+
+    /// Logs an access to the operand of the instruction in progress.
+    #[inline]
+    fn log_operand(&mut self, addr: u16, flags: u16) {
+        if !self.hooked {
+            return;
+        }
+        let indirect = match &self.code_data_logger {
+            Some(logger) if logger.indirect => INDIRECT_DATA,
+            Some(_) => 0,
+            None => return,
+        };
+        self.log_code_data(addr, flags | indirect);
+    }
+
+    /// Reads the operand of an instruction, logging it.
+    pub(crate) fn read_operand(&mut self, addr: u16) -> u8 {
+        self.log_operand(addr, DATA);
+        self.read_bus(addr)
+    }
+
+    /// Writes the operand of an instruction, logging it.
+    pub(crate) fn write_operand(&mut self, addr: u16, data: u8) {
+        self.log_operand(addr, WRITTEN);
+        self.write_bus(addr, data);
+    }
+
+    /// Reads other data an instruction uses, such as pointers, logging it.
+    pub(crate) fn read_data(&mut self, addr: u16) -> u8 {
+        self.log_code_data(addr, DATA);
+        self.read_bus(addr)
+    }
+}
@@ -0,0 +1,334 @@
+use alloc::vec::Vec;
+
+use super::{
+    breakpoints::BreakpointKind,
+    bus::Bus,
+    snapshot::{save_halt, Reader, SnapshotError},
+    status::Status,
+    CPUError, Halt, CPU,
+};
+
+/// Bookkeeping for cycle-stepped execution.
+///
+/// Every cycle of a 6502 is exactly one bus access, and instructions are written as straight-line
+/// code that makes those accesses in hardware order. To stop in the middle of an instruction,
+/// the CPU remembers its state at the start of the instruction and the data seen on the bus so far.
+/// On the next cycle it rewinds, runs the instruction again answering the earlier accesses from
+/// the log without touching the bus, performs the next access for real, and skips the rest.
+#[derive(Clone, Default)]
+pub(crate) struct Replay {
+    /// CPU state at the start of the instruction (or interrupt sequence) in progress
+    start: Option<StartState>,
+    /// Data of the accesses already performed in the instruction in progress
+    log: Vec<u8>,
+    /// Whether bus accesses currently go through the log
+    active: bool,
+    /// Number of accesses the current run answers from the log
+    replayed: usize,
+    /// Index of the next access in the current run of the instruction
+    position: usize,
+}
+
+/// What to do with a bus access while replaying
+pub(crate) enum ReplayStep {
+    /// The access was already performed; here is its data.
+    Replayed(u8),
+    /// This is the access of the current cycle and has to be performed on the bus.
+    Live,
+    /// The access belongs to a future cycle.
+    Skipped,
+}
+
+impl Replay {
+    pub(crate) fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Whether an instruction is in progress
+    pub(crate) fn in_progress(&self) -> bool {
+        self.start.is_some()
+    }
+
+    /// Whether the next access is performed on the bus, rather than replayed or skipped
+    pub(crate) fn next_is_live(&self) -> bool {
+        !self.active || self.position == self.replayed
+    }
+
+    pub(crate) fn next_step(&mut self) -> ReplayStep {
+        let step = match self.position.cmp(&self.replayed) {
+            core::cmp::Ordering::Less => ReplayStep::Replayed(self.log[self.position]),
+            core::cmp::Ordering::Equal => ReplayStep::Live,
+            core::cmp::Ordering::Greater => ReplayStep::Skipped,
+        };
+        self.position += 1;
+        step
+    }
+
+    pub(crate) fn record(&mut self, data: u8) {
+        self.log.push(data);
+    }
+
+    /// Writes the instruction in progress, if any, for a snapshot. Between ticks, that's all
+    /// there is: the run-specific fields are reset on every cycle.
+    pub(crate) fn save(&self, out: &mut Vec<u8>) {
+        let Some(start) = &self.start else {
+            return out.push(0);
+        };
+        out.push(1);
+        out.extend(start.pc.to_le_bytes());
+        out.extend([
+            start.ac,
+            start.x,
+            start.y,
+            start.status,
+            start.stack_pointer,
+            start.waiting as u8,
+        ]);
+        save_halt(start.halted, out);
+        out.extend(start.total_cycles.to_le_bytes());
+        match start.interrupt {
+            Some(vector) => {
+                out.push(1);
+                out.extend(vector.to_le_bytes());
+            }
+            None => out.push(0),
+        }
+        out.push(self.log.len() as u8);
+        out.extend(&self.log);
+    }
+
+    /// Reads what [`save`](Replay::save) wrote.
+    pub(crate) fn load(input: &mut Reader) -> Result<Self, SnapshotError> {
+        if !input.bool()? {
+            return Ok(Self::default());
+        }
+        let start = StartState {
+            pc: input.u16()?,
+            ac: input.u8()?,
+            x: input.u8()?,
+            y: input.u8()?,
+            status: input.u8()?,
+            stack_pointer: input.u8()?,
+            waiting: input.bool()?,
+            halted: input.halt()?,
+            total_cycles: input.u64()?,
+            interrupt: match input.bool()? {
+                true => Some(input.u16()?),
+                false => None,
+            },
+        };
+        let len = input.u8()? as usize;
+        Ok(Self {
+            start: Some(start),
+            log: input.bytes(len)?.to_vec(),
+            ..Self::default()
+        })
+    }
+}
+
+/// Registers at the start of an instruction, to rewind to between cycles
+#[derive(Clone)]
+struct StartState {
+    pc: u16,
+    ac: u8,
+    x: u8,
+    y: u8,
+    status: u8,
+    stack_pointer: u8,
+    halted: Option<Halt>,
+    waiting: bool,
+    total_cycles: u64,
+    /// Vector of the interrupt being served instead of an instruction
+    interrupt: Option<u16>,
+}
+
+impl<T: Bus> CPU<T> {
+    /// Reads from the bus, getting the last value on the data bus back if nothing answers.
+    /// In cycle-stepped mode, the read may be answered from the replay log.
+    pub fn read_bus(&mut self, addr: u16) -> u8 {
+        self.read_bus_watched(addr, true)
+    }
+
+    /// Reads from the bus, checking the read breakpoints if `watched`.
+    #[inline]
+    pub(crate) fn read_bus_watched(&mut self, addr: u16, watched: bool) -> u8 {
+        if self.hooked {
+            return self.read_bus_hooked(addr, watched);
+        }
+        self.data_bus = self.bus.read(addr).unwrap_or(self.data_bus);
+        self.data_bus
+    }
+
+    /// Kept out of the instructions, so that [`run_cycles`](CPU::run_cycles) only pays for one
+    /// check per access when nothing is hooked
+    #[inline(never)]
+    fn read_bus_hooked(&mut self, addr: u16, watched: bool) -> u8 {
+        if self.replay.is_active() {
+            return self.read_bus_replayed(addr, watched);
+        }
+        self.data_bus = self.bus.read(addr).unwrap_or(self.data_bus);
+        if watched {
+            self.watch(BreakpointKind::Read, addr);
+        }
+        self.data_bus
+    }
+
+    #[cold]
+    fn read_bus_replayed(&mut self, addr: u16, watched: bool) -> u8 {
+        match self.replay.next_step() {
+            ReplayStep::Replayed(data) => {
+                self.data_bus = data;
+                data
+            }
+            ReplayStep::Live => {
+                self.data_bus = self.bus.read(addr).unwrap_or(self.data_bus);
+                self.replay.record(self.data_bus);
+                if watched {
+                    self.watch(BreakpointKind::Read, addr);
+                }
+                self.data_bus
+            }
+            ReplayStep::Skipped => 0,
+        }
+    }
+
+    /// Writes to the bus. In cycle-stepped mode, the write only happens on its own cycle.
+    #[inline]
+    pub fn write_bus(&mut self, addr: u16, data: u8) {
+        if self.hooked {
+            return self.write_bus_hooked(addr, data);
+        }
+        self.data_bus = data;
+        self.bus.write(addr, data);
+    }
+
+    /// Kept out of the instructions, like [`read_bus_hooked`](CPU::read_bus_hooked)
+    #[inline(never)]
+    fn write_bus_hooked(&mut self, addr: u16, data: u8) {
+        if self.replay.is_active() {
+            return self.write_bus_replayed(addr, data);
+        }
+        self.data_bus = data;
+        self.bus.write(addr, data);
+        self.watch(BreakpointKind::Write, addr);
+    }
+
+    #[cold]
+    fn write_bus_replayed(&mut self, addr: u16, data: u8) {
+        match self.replay.next_step() {
+            ReplayStep::Replayed(_) => self.data_bus = data,
+            ReplayStep::Live => {
+                self.data_bus = data;
+                self.bus.write(addr, data);
+                self.replay.record(data);
+                self.watch(BreakpointKind::Write, addr);
+            }
+            ReplayStep::Skipped => {}
+        }
+    }
+
+    /// Reads memory without side effects, seeing open bus where the bus can't answer.
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.bus.peek(addr).unwrap_or(self.data_bus)
+    }
+
+    /// Runs a single cycle, performing exactly one bus access.
+    ///
+    /// Returns `true` if an instruction or interrupt sequence was completed on this cycle.
+    pub(crate) fn tick_cycle(&mut self) -> Result<bool, CPUError> {
+        if !self.replay.in_progress() && self.cycles_left > 0 {
+            // Leftover cycles of RESET or of an instruction run with `execute`.
+            self.cycles_left -= 1;
+            self.bus.tick();
+            return Ok(self.cycles_left == 0);
+        }
+        if self.halted.is_some() || (!self.replay.in_progress() && self.still_waiting()) {
+            self.total_cycles += 1;
+            self.bus.tick();
+            return Ok(false);
+        }
+        let start = match self.replay.start.take() {
+            Some(start) => {
+                self.rewind(&start);
+                start
+            }
+            None => {
+                // Breakpoints, traces, logs and profiles are handled once, before the first cycle.
+                let interrupt = self.take_interrupt();
+                match interrupt {
+                    Some(vector) => self.profile_interrupt(vector),
+                    None => {
+                        if self.break_before_instruction() {
+                            return Ok(false);
+                        }
+                        self.trace();
+                        self.log_debug();
+                        self.profile_instruction();
+                    }
+                }
+                StartState {
+                    interrupt,
+                    pc: self.pc,
+                    ac: self.ac,
+                    x: self.x,
+                    y: self.y,
+                    status: self.status.byte,
+                    stack_pointer: self.stack_pointer,
+                    halted: self.halted,
+                    waiting: self.waiting,
+                    total_cycles: self.total_cycles,
+                }
+            }
+        };
+
+        self.replay.active = true;
+        self.replay.replayed = self.replay.log.len();
+        self.replay.position = 0;
+        let result = match start.interrupt {
+            Some(vector) => {
+                self.interrupt(vector);
+                self.bus_fault().map_err(|kind| self.error(start.pc, kind))
+            }
+            None => self.execute_instruction(),
+        };
+        self.replay.active = false;
+
+        let performed = self.replay.log.len();
+        let finished = result.is_err() || self.replay.position == performed;
+        self.cycles_left = 0;
+        self.total_cycles = start.total_cycles + performed as u64;
+        if finished {
+            self.replay.log.clear();
+        } else {
+            self.rewind(&start);
+            self.replay.start = Some(start);
+        }
+        result?;
+        self.bus.tick();
+        Ok(finished)
+    }
+
+    /// Runs cycles until the instruction in progress (or the next one) completes.
+    pub(crate) fn finish_instruction(&mut self) -> Result<(), CPUError> {
+        while !self.tick_cycle()? {
+            if self.halted.is_some()
+                || self.waiting
+                || self.breakpoints.stopped_before_instruction()
+            {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn rewind(&mut self, start: &StartState) {
+        self.pc = start.pc;
+        self.ac = start.ac;
+        self.x = start.x;
+        self.y = start.y;
+        self.status = Status { byte: start.status };
+        self.stack_pointer = start.stack_pointer;
+        self.halted = start.halted;
+        self.waiting = start.waiting;
+    }
+}
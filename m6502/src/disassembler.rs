@@ -0,0 +1,214 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt::{self, Write};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use super::{
+    addressing_modes::{Access, AddressingMode},
+    bus::Bus,
+    opcode_table::{self, OpcodeEntry},
+    variant::Variant,
+    CPU,
+};
+
+/// Names for addresses, substituted for them in disassembled operands
+pub trait Labels {
+    fn label(&self, addr: u16) -> Option<&str>;
+}
+
+/// No labels at all
+impl Labels for () {
+    fn label(&self, _addr: u16) -> Option<&str> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl Labels for HashMap<u16, String> {
+    fn label(&self, addr: u16) -> Option<&str> {
+        self.get(&addr).map(String::as_str)
+    }
+}
+
+impl Labels for BTreeMap<u16, String> {
+    fn label(&self, addr: u16) -> Option<&str> {
+        self.get(&addr).map(String::as_str)
+    }
+}
+
+/// A decoded instruction, or a data byte if the opcode is unknown
+#[derive(Copy, Clone, Debug)]
+pub struct Disassembly {
+    /// Address of the first byte
+    pub address: u16,
+    /// The opcode, or `None` for a byte that isn't one
+    pub entry: Option<OpcodeEntry>,
+    /// The opcode and operand bytes; only the first [`size`](Disassembly::size) are used.
+    pub raw: [u8; 3],
+}
+
+impl Disassembly {
+    /// Decodes the instruction in `raw` (opcode first) located at `address`.
+    pub fn new(address: u16, raw: [u8; 3], entry: Option<OpcodeEntry>) -> Self {
+        Self {
+            address,
+            entry,
+            raw,
+        }
+    }
+
+    /// Number of bytes taken by the instruction
+    pub fn size(&self) -> u16 {
+        self.entry.map_or(1, |entry| entry.bytes as u16)
+    }
+
+    /// The opcode and operand bytes
+    pub fn bytes(&self) -> &[u8] {
+        &self.raw[..self.size() as usize]
+    }
+
+    /// Address of the instruction that follows
+    pub fn next(&self) -> u16 {
+        self.address.wrapping_add(self.size())
+    }
+
+    /// The operand as written after the opcode: an 8-bit value or address, or a 16-bit address
+    pub fn operand(&self) -> u16 {
+        match self.size() {
+            2 => self.raw[1] as u16,
+            3 => u16::from_le_bytes([self.raw[1], self.raw[2]]),
+            _ => 0,
+        }
+    }
+
+    /// Where a branch goes if it's taken
+    pub fn branch_target(&self) -> Option<u16> {
+        let offset = match self.entry?.addressing_mode {
+            AddressingMode::Relative => self.raw[1],
+            AddressingMode::ZeroPageRelative => self.raw[2],
+            _ => return None,
+        };
+        Some(self.next().wrapping_add_signed(offset as i8 as i16))
+    }
+
+    /// Formats the instruction in standard syntax, using `labels` for addresses that have one.
+    pub fn format_with(&self, labels: &impl Labels) -> String {
+        let mut text = String::new();
+        // Writing to a String never fails.
+        let _ = self.write_with(&mut text, labels);
+        text
+    }
+
+    /// Writes the instruction like [`format_with`](Disassembly::format_with), without
+    /// allocating.
+    pub fn write_with(&self, f: &mut impl Write, labels: &impl Labels) -> fmt::Result {
+        let Some(entry) = self.entry else {
+            return write!(f, ".byte ${:02X}", self.raw[0]);
+        };
+        write!(f, "{:?}", entry.instruction)?;
+        let low = self.raw[1];
+        let word = self.operand();
+        let target = self.branch_target().unwrap_or_default();
+        let (prefix, address, zero_page, suffix) = match entry.addressing_mode {
+            // Shifts, INC and DEC without an operand work on the accumulator.
+            AddressingMode::Implied => {
+                if entry.access == Access::ReadModifyWrite {
+                    f.write_str(" A")?;
+                }
+                return Ok(());
+            }
+            AddressingMode::Immediate => return write!(f, " #${low:02X}"),
+            AddressingMode::ZeroPage => ("", low as u16, true, ""),
+            AddressingMode::ZeroPageX => ("", low as u16, true, ",X"),
+            AddressingMode::ZeroPageY => ("", low as u16, true, ",Y"),
+            AddressingMode::Absolute => ("", word, false, ""),
+            AddressingMode::AbsoluteX => ("", word, false, ",X"),
+            AddressingMode::AbsoluteY => ("", word, false, ",Y"),
+            AddressingMode::Indirect => ("(", word, false, ")"),
+            AddressingMode::IndirectX => ("(", low as u16, true, ",X)"),
+            AddressingMode::IndirectY => ("(", low as u16, true, "),Y"),
+            AddressingMode::ZeroPageIndirect => ("(", low as u16, true, ")"),
+            AddressingMode::AbsoluteIndirectX => ("(", word, false, ",X)"),
+            AddressingMode::Relative => ("", target, false, ""),
+            AddressingMode::ZeroPageRelative => {
+                f.write_char(' ')?;
+                write_address(f, labels, low as u16, true)?;
+                f.write_char(',')?;
+                return write_address(f, labels, target, false);
+            }
+        };
+        write!(f, " {prefix}")?;
+        write_address(f, labels, address, zero_page)?;
+        f.write_str(suffix)
+    }
+}
+
+/// Writes an address as its label, or in hex with two digits in the zero page and four elsewhere.
+fn write_address(
+    f: &mut impl Write,
+    labels: &impl Labels,
+    addr: u16,
+    zero_page: bool,
+) -> fmt::Result {
+    match labels.label(addr) {
+        Some(label) => f.write_str(label),
+        None if zero_page => write!(f, "${addr:02X}"),
+        None => write!(f, "${addr:04X}"),
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_with(f, &())
+    }
+}
+
+/// Decodes the instruction at `addr` in `bus`, reading it with [`peek`](Bus::peek) so no device
+/// notices. Unmapped bytes read as zero.
+pub fn disassemble(
+    bus: &impl Bus,
+    addr: u16,
+    variant: Variant,
+    undocumented_opcodes: bool,
+) -> Disassembly {
+    decode_at(addr, variant, undocumented_opcodes, |addr| {
+        bus.peek(addr).unwrap_or(0)
+    })
+}
+
+/// Decodes the instruction at `addr`, reading its bytes with `peek`.
+fn decode_at(
+    addr: u16,
+    variant: Variant,
+    undocumented_opcodes: bool,
+    peek: impl Fn(u16) -> u8,
+) -> Disassembly {
+    let raw = core::array::from_fn(|i| peek(addr.wrapping_add(i as u16)));
+    Disassembly::new(
+        addr,
+        raw,
+        opcode_table::decode(variant, undocumented_opcodes, raw[0]),
+    )
+}
+
+impl<T: Bus> CPU<T> {
+    /// Decodes the instruction at `addr` with the opcodes enabled for this CPU, like
+    /// [`disassemble`], but with unmapped bytes read as [`peek`](CPU::peek) does.
+    pub fn disassemble(&self, addr: u16) -> Disassembly {
+        decode_at(addr, self.variant, self.undocumented_opcodes, |addr| {
+            self.peek(addr)
+        })
+    }
+
+    /// Decodes the instructions starting between `start` and `end`, inclusive.
+    pub fn disassemble_range(&self, start: u16, end: u16) -> Vec<Disassembly> {
+        let mut instructions = Vec::new();
+        let mut addr = start as u32;
+        while addr <= end as u32 {
+            let instruction = self.disassemble(addr as u16);
+            addr += instruction.size() as u32;
+            instructions.push(instruction);
+        }
+        instructions
+    }
+}
@@ -1,36 +1,134 @@
-use super::{bus::Bus, instructions::Instruction, opcode_table::OPCODE_TABLE, CPUError, CPU};
+use super::{
+    addressing_modes::{crosses_page, Access, OperandData},
+    breakpoints::Break,
+    bus::Bus,
+    instructions::Instruction,
+    interrupts::IRQ_VECTOR,
+    opcode_table::{
+        self, CycleRule, OpcodeEntry, CMOS_OPCODE_TABLE, OPCODE_TABLE, UNDOCUMENTED_OPCODE_TABLE,
+    },
+    variant::Variant,
+    CPUError, ErrorKind, Halt, CPU,
+};
+
+/// The constant ORed into the accumulator by the unstable XAA and LXA opcodes.
+///
+/// On real chips it depends on the individual chip and even its temperature; 0xEE is the most
+/// common value.
+pub const UNSTABLE_MAGIC: u8 = 0xEE;
 
 impl<T: Bus> CPU<T> {
-    pub fn execute(&mut self) -> Result<(), CPUError> {
-        // log::trace!("{:02x} {:02x} {:02x}", self.bus.read(self.pc as usize - 2), self.bus.read(self.pc as usize - 1), self.bus.read(0x0600));
-        let opcode = self.fetch_byte();
-        let opcode_data = OPCODE_TABLE[opcode as usize].ok_or(CPUError::NoInstruction(opcode))?;
-        let operand = self.fetch_op_data(opcode_data.addressing_mode);
-        let opfmt = {
-            match operand {
-                crate::addressing_modes::OperandData::Implied => "".into(),
-                crate::addressing_modes::OperandData::Literal(x) => format!("0x{x:02x}"),
-                crate::addressing_modes::OperandData::Address(x) => format!("(0x{x:04x})"),
+    /// Executes the next instruction, or serves a pending interrupt.
+    ///
+    /// In cycle-stepped mode, this finishes the instruction in progress instead, ticking the bus
+    /// once per cycle.
+    ///
+    /// Returns the breakpoint that stopped the CPU, if any. An execute breakpoint stops it before
+    /// the instruction, which then runs on the next call; read and write breakpoints stop it
+    /// after the instruction.
+    pub fn execute(&mut self) -> Result<Option<Break>, CPUError> {
+        let pc = self.pc;
+        let result = if self.cycle_stepped {
+            // A halted or waiting CPU spends a cycle there, like `tick` does.
+            self.finish_instruction()
+        } else if self.halted.is_some() || self.still_waiting() {
+            self.cycles_left = 1;
+            self.total_cycles += 1;
+            return Ok(None);
+        } else if self.poll_interrupts() {
+            self.bus_fault().map_err(|kind| self.error(pc, kind))
+        } else if self.break_before_instruction() {
+            Ok(())
+        } else {
+            self.trace();
+            self.log_debug();
+            self.profile_instruction();
+            self.execute_instruction()
+        };
+        let hit = self.breakpoints.take_hit();
+        result.map(|()| hit)
+    }
+
+    /// Looks up an opcode in the tables enabled for this CPU.
+    pub fn decode(&self, opcode: u8) -> Option<OpcodeEntry> {
+        opcode_table::decode(self.variant, self.undocumented_opcodes, opcode)
+    }
+
+    /// Handlers of the NMOS opcodes, documented or not
+    const NMOS_HANDLERS: [Handler<T>; 256] = handlers!(false);
+    /// Handlers of the 65C02 opcodes
+    const CMOS_HANDLERS: [Handler<T>; 256] = handlers!(true);
+
+    /// Fetches and executes one instruction, making the same bus accesses as the real chip.
+    #[inline(always)]
+    pub(crate) fn execute_instruction(&mut self) -> Result<(), CPUError> {
+        let instruction_pc = self.pc;
+        let opcode = self.fetch_opcode();
+        let handlers = match self.variant {
+            Variant::Cmos65C02 => &Self::CMOS_HANDLERS,
+            Variant::Nmos | Variant::Ricoh2A03 => &Self::NMOS_HANDLERS,
+        };
+        handlers[opcode as usize](self, instruction_pc)
+            .and_then(|()| self.bus_fault())
+            .map_err(|kind| self.error(instruction_pc, kind))
+    }
+
+    /// Executes `OPCODE`, once fetched. It's decoded at compile time, so that every check on
+    /// the instruction and addressing mode below is resolved for each opcode by the compiler.
+    fn handle<const OPCODE: u8, const CMOS: bool>(
+        &mut self,
+        instruction_pc: u16,
+    ) -> Result<(), ErrorKind> {
+        let (entry, undocumented) = const {
+            let code = OPCODE as usize;
+            match (CMOS, OPCODE_TABLE[code], UNDOCUMENTED_OPCODE_TABLE[code]) {
+                (true, ..) => (CMOS_OPCODE_TABLE[code], false),
+                (false, Some(entry), _) => (Some(entry), false),
+                (false, None, entry) => (entry, true),
             }
         };
-        log::debug!(
-            "PC:0x{:04x} {:?} ({:?}, {})",
-            self.pc - opcode_data.bytes as u16,
-            opcode_data.instruction,
-            opcode_data.addressing_mode,
-            opfmt
-        );
+        match entry {
+            Some(entry) if !undocumented || self.undocumented_opcodes => {
+                self.run_instruction(instruction_pc, OPCODE, entry)
+            }
+            _ => Err(ErrorKind::NoInstruction(OPCODE)),
+        }
+    }
+
+    /// Executes an instruction whose opcode was fetched from `instruction_pc`.
+    #[inline(always)]
+    fn run_instruction(
+        &mut self,
+        instruction_pc: u16,
+        opcode: u8,
+        opcode_data: OpcodeEntry,
+    ) -> Result<(), ErrorKind> {
+        let (operand, page_crossed) = match opcode_data.instruction {
+            // JSR pushes the return address between fetching the two bytes of the target.
+            Instruction::JSR => (OperandData::Implied, false),
+            // The single-cycle NOPs of the 65C02 don't even read the next byte.
+            Instruction::NOP if opcode_data.cycles == 1 => (OperandData::Implied, false),
+            _ => {
+                let access = match opcode_data.access {
+                    // Indexed shifts on the 65C02 only take the extra cycle when crossing a page.
+                    Access::ReadModifyWrite
+                        if matches!(opcode_data.cycle_rule, CycleRule::AddOnCross) =>
+                    {
+                        Access::Read
+                    }
+                    access => access,
+                };
+                self.fetch_op_data(opcode_data.addressing_mode, access)
+            }
+        };
+        self.log_instruction(&opcode_data);
+        let mut branch_taken = false;
+        let mut decimal_cycle = false;
         match opcode_data.instruction {
             Instruction::ADC => {
                 let rhs = operand.value(self)?;
-                let (new, carry) = self.ac.carrying_add(rhs, self.status.carry());
-                self.status
-                    .set_overflow(((self.ac ^ new) & (rhs ^ new) & 0x80) != 0);
-                self.ac = new;
-                self.status.set_carry(carry);
-                self.status.set_zero(self.ac == 0);
-                self.status.set_negative((self.ac & 0b10000000) != 0);
-                // TODO: bcd
+                self.add_with_carry(rhs);
+                decimal_cycle = self.cmos_decimal_cycle();
             }
             Instruction::AND => {
                 let rhs = operand.value(self)?;
@@ -39,104 +137,57 @@ impl<T: Bus> CPU<T> {
                 self.status.set_negative((self.ac & 0b10000000) != 0);
             }
             Instruction::ASL => {
-                let lhs = operand.value(self).unwrap_or(self.ac);
-                let (new, _) = lhs.overflowing_shl(1);
-                operand.write(self, new)?;
-                self.status.set_carry(lhs & 0b10000000 != 0);
-                self.status.set_zero(new == 0);
-                self.status.set_negative((new & 0b10000000) != 0);
-            }
-            Instruction::BCC => {
-                let target = operand.address()?; // TODO: Change get_address handling.
-                if !self.status.carry() {
-                    self.pc = target;
-                }
-            }
-            Instruction::BCS => {
-                let target = operand.address()?;
-                if self.status.carry() {
-                    self.pc = target;
-                }
-            }
-            Instruction::BEQ => {
-                let target = operand.address()?;
-                if self.status.zero() {
-                    log::info!("BEQ jumping");
-                    self.pc = target;
-                }
+                let value = operand.value(self)?;
+                let result = self.shift_left(value);
+                operand.write_modified(self, value, result)?;
             }
+            Instruction::BCC => branch_taken = self.branch(!self.status.carry(), operand)?,
+            Instruction::BCS => branch_taken = self.branch(self.status.carry(), operand)?,
+            Instruction::BEQ => branch_taken = self.branch(self.status.zero(), operand)?,
             Instruction::BIT => {
                 let o = operand.value(self)?;
                 self.status.set_zero((o & self.ac) == 0);
-                self.status.byte |= o & 0b11000000;
-            }
-            Instruction::BMI => {
-                let target = operand.address()?;
-                if self.status.negative() {
-                    self.pc = target;
-                }
-            }
-            Instruction::BNE => {
-                let target = operand.address()?;
-                if !self.status.zero() {
-                    self.pc = target;
-                }
-            }
-            Instruction::BPL => {
-                let target = operand.address()?;
-                if !self.status.negative() {
-                    self.pc = target;
-                }
-            }
-            // Instruction::BRK => todo!(),
-            Instruction::BVC => {
-                let target = operand.address()?;
-                if !self.status.overflow() {
-                    self.pc = target;
+                // BIT immediate on the 65C02 only affects Z.
+                if let OperandData::Address(_) = operand {
+                    self.status.set_negative((o & 0b10000000) != 0);
+                    self.status.set_overflow((o & 0b01000000) != 0);
                 }
             }
-            Instruction::BVS => {
-                let target = operand.address()?;
-                if self.status.overflow() {
-                    self.pc = target;
+            Instruction::BMI => branch_taken = self.branch(self.status.negative(), operand)?,
+            Instruction::BNE => branch_taken = self.branch(!self.status.zero(), operand)?,
+            Instruction::BPL => branch_taken = self.branch(!self.status.negative(), operand)?,
+            Instruction::BRK => {
+                // BRK is followed by a padding byte, so the return address is PC + 2.
+                self.push_word(self.pc.wrapping_add(1));
+                self.push_byte(self.status.byte | 0b00110000);
+                self.status.set_interrupt_disabled(true);
+                if self.variant.is_cmos() {
+                    self.status.set_decimal(false);
                 }
+                self.pc = self.read_vector(IRQ_VECTOR);
             }
+            Instruction::BVC => branch_taken = self.branch(!self.status.overflow(), operand)?,
+            Instruction::BVS => branch_taken = self.branch(self.status.overflow(), operand)?,
             Instruction::CLC => self.status.set_carry(false),
             Instruction::CLD => self.status.set_decimal(false),
             Instruction::CLI => self.status.set_interrupt_disabled(false),
             Instruction::CLV => self.status.set_overflow(false),
             Instruction::CMP => {
                 let rhs = operand.value(self)?;
-                let (res, borrow) = self.ac.borrowing_sub(rhs, false);
-                self.status.set_carry(!borrow);
-                self.status.set_zero(res == 0);
-                self.status.set_negative((res & 0b10000000) != 0);
-                log::info!(
-                    "CMP: A(0x{:02x}) with 0x{rhs:02x} - C={} Z={} N={}",
-                    self.ac,
-                    self.status.carry(),
-                    self.status.zero(),
-                    self.status.negative()
-                );
+                self.compare(self.ac, rhs);
             }
             Instruction::CPX => {
                 let rhs = operand.value(self)?;
-                let (res, borrow) = self.x.borrowing_sub(rhs, false);
-                self.status.set_carry(borrow);
-                self.status.set_zero(res == 0);
-                self.status.set_negative((res & 0b10000000) != 0);
+                self.compare(self.x, rhs);
             }
             Instruction::CPY => {
                 let rhs = operand.value(self)?;
-                let (res, borrow) = self.y.borrowing_sub(rhs, false);
-                self.status.set_carry(borrow);
-                self.status.set_zero(res == 0);
-                self.status.set_negative((res & 0b10000000) != 0);
+                self.compare(self.y, rhs);
             }
             Instruction::DEC => {
                 let m = operand.value(self)?;
                 let res = m.wrapping_sub(1);
-                operand.write(self, res)?;
+                operand.write_modified(self, m, res)?;
                 self.status.set_zero(res == 0);
                 self.status.set_negative((res & 0b10000000) != 0);
             }
@@ -159,7 +210,7 @@ impl<T: Bus> CPU<T> {
             Instruction::INC => {
                 let m = operand.value(self)?;
                 let res = m.wrapping_add(1);
-                operand.write(self, res)?;
+                operand.write_modified(self, m, res)?;
                 self.status.set_zero(res == 0);
                 self.status.set_negative((res & 0b10000000) != 0);
             }
@@ -175,12 +226,17 @@ impl<T: Bus> CPU<T> {
             }
             Instruction::JMP => {
                 self.pc = operand.address()?;
-                log::trace!("0x{:04x}", self.pc)
+                #[cfg(feature = "log")]
+                log::trace!("0x{:04x}", self.pc);
             }
             Instruction::JSR => {
-                let target = operand.address()?;
+                let low = self.fetch_byte() as u16;
+                self.dummy_stack_read();
+                // The pushed return address points to the last byte of the JSR instruction,
+                // which is only fetched afterwards.
                 self.push_word(self.pc);
-                self.pc = target;
+                let high = self.fetch_byte() as u16;
+                self.pc = low | (high << 8);
             }
             Instruction::LDA => {
                 self.ac = operand.value(self)?;
@@ -198,15 +254,22 @@ impl<T: Bus> CPU<T> {
                 self.status.set_negative((self.y & 0b10000000) != 0);
             }
             Instruction::LSR => {
-                let x = operand.value(self)?;
-                let new = x & 1 != 0;
-                let (res, _) = x.overflowing_shr(1);
-                operand.write(self, res)?;
-                self.status.set_overflow(new);
-                self.status.set_zero(res == 0);
-                self.status.set_carry(new);
+                let value = operand.value(self)?;
+                let result = self.shift_right(value);
+                operand.write_modified(self, value, result)?;
+            }
+            Instruction::NOP => {
+                // Undocumented NOPs with a memory operand still read it.
+                if let OperandData::Address(_) = operand {
+                    operand.value(self)?;
+                    // 0x5C of the 65C02 keeps reading for four more cycles.
+                    if opcode_data.cycles == 8 {
+                        for _ in 0..4 {
+                            operand.value(self)?;
+                        }
+                    }
+                }
             }
-            Instruction::NOP => {}
             Instruction::ORA => {
                 self.ac |= operand.value(self)?;
                 self.status.set_zero(self.ac == 0);
@@ -215,64 +278,61 @@ impl<T: Bus> CPU<T> {
             Instruction::PHA => {
                 self.push_byte(self.ac);
             }
-            Instruction::PHP => self.push_byte(self.status.byte),
+            // The pushed copy always has B and the unused bit set.
+            Instruction::PHP => self.push_byte(self.status.byte | 0b00110000),
             Instruction::PLA => {
+                self.dummy_stack_read();
                 self.ac = self.pull_byte();
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
             }
             Instruction::PLP => {
-                self.status.byte = self.pull_byte();
+                self.dummy_stack_read();
+                let old_status = self.pull_byte();
+                self.status.byte = (old_status & 0b11001111) | (self.status.byte & 0b00110000);
             }
             Instruction::ROL => {
                 let value = operand.value(self)?;
-                let result = value.rotate_left(1);
-                operand.write(self, result)?;
-                self.status.set_zero(result == 0);
-                self.status.set_negative((result & 0b10000000) != 0);
-                self.status.set_carry((result & 0b10000000) != 0);
+                let result = self.rotate_left(value);
+                operand.write_modified(self, value, result)?;
             }
             Instruction::ROR => {
                 let value = operand.value(self)?;
-                let result = value.rotate_right(1);
-                operand.write(self, result)?;
-                self.status.set_zero(result == 0);
-                self.status.set_negative((result & 0b10000000) != 0);
-                self.status.set_carry((result & 1) != 0);
+                let result = self.rotate_right(value);
+                operand.write_modified(self, value, result)?;
             }
             Instruction::RTI => {
+                self.dummy_stack_read();
                 let old_status = self.pull_byte();
                 self.status.byte = (old_status & 0b11001111) | (self.status.byte & 0b00110000);
                 let old_pc = self.pull_word();
                 self.pc = old_pc;
             }
             Instruction::RTS => {
-                self.pc = self.pull_word(); //TODO: Check if it is correct.
-                                            // log::debug!("RTS {:04x}", self.pc)
+                self.dummy_stack_read();
+                self.pc = self.pull_word();
+                // The pulled address is the last byte of the JSR, which is read and skipped.
+                self.fetch_byte();
             }
             Instruction::SBC => {
                 let rhs = operand.value(self)?;
-                let (new, carry) = self.ac.borrowing_sub(rhs, !self.status.carry());
-                self.status
-                    .set_overflow(((self.ac ^ rhs) & 0x80 != 0) && ((self.ac ^ new) & 0x80) != 0);
-                // log::error!("SBC overflow unimplemented!");
-                self.ac = new;
-                self.status.set_carry(!carry);
-                self.status.set_zero(self.ac == 0);
-                self.status.set_negative((self.ac & 0b10000000) != 0);
+                self.subtract_with_carry(rhs);
+                decimal_cycle = self.cmos_decimal_cycle();
             }
             Instruction::SEC => self.status.set_carry(true),
             Instruction::SED => self.status.set_decimal(true),
             Instruction::SEI => self.status.set_interrupt_disabled(true),
             Instruction::STA => {
                 let addr = operand.address()?;
-                self.bus.write(addr as usize, self.ac);
+                self.write_operand(addr, self.ac);
             }
             Instruction::STX => {
                 let addr = operand.address()?;
-                self.bus.write(addr as usize, self.x);
+                self.write_operand(addr, self.x);
             }
             Instruction::STY => {
                 let addr = operand.address()?;
-                self.bus.write(addr as usize, self.y);
+                self.write_operand(addr, self.y);
             }
             Instruction::TAX => {
                 self.x = self.ac;
@@ -294,20 +354,497 @@ impl<T: Bus> CPU<T> {
                 self.status.set_zero(self.ac == 0);
                 self.status.set_negative((self.ac & 0b10000000) != 0);
             }
-            Instruction::TXS => {
-                self.stack_pointer = self.x;
-                self.status.set_zero(self.stack_pointer == 0);
-                self.status
-                    .set_negative((self.stack_pointer & 0b10000000) != 0);
-            }
+            Instruction::TXS => self.stack_pointer = self.x,
             Instruction::TYA => {
                 self.ac = self.y;
                 self.status.set_zero(self.ac == 0);
                 self.status.set_negative((self.ac & 0b10000000) != 0);
             }
-            _ => return Err(CPUError::UnimplementedInstruction(opcode_data.instruction)),
+            Instruction::ALR => {
+                let rhs = operand.value(self)?;
+                self.ac = self.shift_right(self.ac & rhs);
+            }
+            Instruction::ANC => {
+                self.ac &= operand.value(self)?;
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
+                self.status.set_carry((self.ac & 0b10000000) != 0);
+            }
+            Instruction::ARR => {
+                let rhs = operand.value(self)?;
+                self.and_rotate_right(rhs);
+            }
+            Instruction::AXS => {
+                let rhs = operand.value(self)?;
+                let (res, borrow) = (self.ac & self.x).borrowing_sub(rhs, false);
+                self.x = res;
+                self.status.set_carry(!borrow);
+                self.status.set_zero(self.x == 0);
+                self.status.set_negative((self.x & 0b10000000) != 0);
+            }
+            Instruction::DCP => {
+                let m = operand.value(self)?;
+                let res = m.wrapping_sub(1);
+                operand.write_modified(self, m, res)?;
+                self.compare(self.ac, res);
+            }
+            Instruction::ISC => {
+                let m = operand.value(self)?;
+                let res = m.wrapping_add(1);
+                operand.write_modified(self, m, res)?;
+                self.subtract_with_carry(res);
+            }
+            Instruction::JAM => {
+                self.halted = Some(Halt::Jam {
+                    pc: instruction_pc,
+                    opcode,
+                });
+                self.pc = instruction_pc;
+            }
+            Instruction::LAS => {
+                let value = operand.value(self)? & self.stack_pointer;
+                self.ac = value;
+                self.x = value;
+                self.stack_pointer = value;
+                self.status.set_zero(value == 0);
+                self.status.set_negative((value & 0b10000000) != 0);
+            }
+            Instruction::LAX => {
+                self.ac = operand.value(self)?;
+                self.x = self.ac;
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
+            }
+            Instruction::LXA => {
+                self.ac = (self.ac | UNSTABLE_MAGIC) & operand.value(self)?;
+                self.x = self.ac;
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
+            }
+            Instruction::RLA => {
+                let value = operand.value(self)?;
+                let result = self.rotate_left(value);
+                operand.write_modified(self, value, result)?;
+                self.ac &= result;
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
+            }
+            Instruction::RRA => {
+                let value = operand.value(self)?;
+                let result = self.rotate_right(value);
+                operand.write_modified(self, value, result)?;
+                self.add_with_carry(result);
+            }
+            Instruction::SAX => {
+                let addr = operand.address()?;
+                self.write_operand(addr, self.ac & self.x);
+            }
+            Instruction::SHA => self.store_and_high(self.ac & self.x, operand, page_crossed)?,
+            Instruction::SHX => self.store_and_high(self.x, operand, page_crossed)?,
+            Instruction::SHY => self.store_and_high(self.y, operand, page_crossed)?,
+            Instruction::SLO => {
+                let value = operand.value(self)?;
+                let result = self.shift_left(value);
+                operand.write_modified(self, value, result)?;
+                self.ac |= result;
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
+            }
+            Instruction::SRE => {
+                let value = operand.value(self)?;
+                let result = self.shift_right(value);
+                operand.write_modified(self, value, result)?;
+                self.ac ^= result;
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
+            }
+            Instruction::TAS => {
+                self.stack_pointer = self.ac & self.x;
+                self.store_and_high(self.stack_pointer, operand, page_crossed)?;
+            }
+            Instruction::XAA => {
+                self.ac = (self.ac | UNSTABLE_MAGIC) & self.x & operand.value(self)?;
+                self.status.set_zero(self.ac == 0);
+                self.status.set_negative((self.ac & 0b10000000) != 0);
+            }
+            Instruction::BRA => branch_taken = self.branch(true, operand)?,
+            Instruction::PHX => self.push_byte(self.x),
+            Instruction::PHY => self.push_byte(self.y),
+            Instruction::PLX => {
+                self.dummy_stack_read();
+                self.x = self.pull_byte();
+                self.status.set_zero(self.x == 0);
+                self.status.set_negative((self.x & 0b10000000) != 0);
+            }
+            Instruction::PLY => {
+                self.dummy_stack_read();
+                self.y = self.pull_byte();
+                self.status.set_zero(self.y == 0);
+                self.status.set_negative((self.y & 0b10000000) != 0);
+            }
+            Instruction::STP => {
+                self.read_bus(self.pc);
+                self.halted = Some(Halt::Jam {
+                    pc: instruction_pc,
+                    opcode,
+                });
+            }
+            Instruction::STZ => {
+                let addr = operand.address()?;
+                self.write_operand(addr, 0);
+            }
+            Instruction::TRB => {
+                let m = operand.value(self)?;
+                operand.write_modified(self, m, m & !self.ac)?;
+                self.status.set_zero((m & self.ac) == 0);
+            }
+            Instruction::TSB => {
+                let m = operand.value(self)?;
+                operand.write_modified(self, m, m | self.ac)?;
+                self.status.set_zero((m & self.ac) == 0);
+            }
+            Instruction::WAI => {
+                self.read_bus(self.pc);
+                self.waiting = true;
+            }
+            Instruction::RMB0
+            | Instruction::RMB1
+            | Instruction::RMB2
+            | Instruction::RMB3
+            | Instruction::RMB4
+            | Instruction::RMB5
+            | Instruction::RMB6
+            | Instruction::RMB7 => {
+                let m = operand.value(self)?;
+                operand.write_modified(self, m, m & !bit_of(opcode))?;
+            }
+            Instruction::SMB0
+            | Instruction::SMB1
+            | Instruction::SMB2
+            | Instruction::SMB3
+            | Instruction::SMB4
+            | Instruction::SMB5
+            | Instruction::SMB6
+            | Instruction::SMB7 => {
+                let m = operand.value(self)?;
+                operand.write_modified(self, m, m | bit_of(opcode))?;
+            }
+            Instruction::BBR0
+            | Instruction::BBR1
+            | Instruction::BBR2
+            | Instruction::BBR3
+            | Instruction::BBR4
+            | Instruction::BBR5
+            | Instruction::BBR6
+            | Instruction::BBR7 => {
+                let m = operand.value(self)?;
+                branch_taken = self.branch((m & bit_of(opcode)) == 0, operand)?;
+            }
+            Instruction::BBS0
+            | Instruction::BBS1
+            | Instruction::BBS2
+            | Instruction::BBS3
+            | Instruction::BBS4
+            | Instruction::BBS5
+            | Instruction::BBS6
+            | Instruction::BBS7 => {
+                let m = operand.value(self)?;
+                branch_taken = self.branch((m & bit_of(opcode)) != 0, operand)?;
+            }
+        }
+        let cycles = opcode_data.cycles
+            + opcode_data
+                .cycle_rule
+                .extra_cycles(page_crossed, branch_taken)
+            + decimal_cycle as u8;
+        self.cycles_left = cycles;
+        self.total_cycles += cycles as u64;
+        if matches!(opcode_data.access, Access::Branch | Access::Jump)
+            && self.pc == instruction_pc
+            && self.trap_self_jumps
+        {
+            self.halted = Some(Halt::Trap { pc: instruction_pc });
         }
-        self.cycles_left = opcode_data.cycles; // TODO: Model precise cycle behavior
         Ok(())
     }
 }
+
+impl<T: Bus> CPU<T> {
+    /// Logs the instruction about to run at debug level. Like the tracer, it's called once per
+    /// instruction, not on every replay of it in cycle-stepped mode.
+    pub(crate) fn log_debug(&self) {
+        #[cfg(feature = "log")]
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!("PC:0x{:04x} {}", self.pc, self.disassemble(self.pc));
+        }
+    }
+}
+
+/// Executes the instruction of an opcode fetched from the given address
+type Handler<T> = fn(&mut CPU<T>, u16) -> Result<(), ErrorKind>;
+
+/// The handlers of all 256 opcodes, for the 65C02 or not
+macro_rules! handlers {
+    ($cmos:literal) => {
+        flatten([
+            handlers!($cmos, 0x0),
+            handlers!($cmos, 0x1),
+            handlers!($cmos, 0x2),
+            handlers!($cmos, 0x3),
+            handlers!($cmos, 0x4),
+            handlers!($cmos, 0x5),
+            handlers!($cmos, 0x6),
+            handlers!($cmos, 0x7),
+            handlers!($cmos, 0x8),
+            handlers!($cmos, 0x9),
+            handlers!($cmos, 0xA),
+            handlers!($cmos, 0xB),
+            handlers!($cmos, 0xC),
+            handlers!($cmos, 0xD),
+            handlers!($cmos, 0xE),
+            handlers!($cmos, 0xF),
+        ])
+    };
+    ($cmos:literal, $high:literal) => {
+        [
+            Self::handle::<{ $high << 4 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x1 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x2 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x3 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x4 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x5 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x6 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x7 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x8 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0x9 }, $cmos>,
+            Self::handle::<{ $high << 4 | 0xA }, $cmos>,
+            Self::handle::<{ $high << 4 | 0xB }, $cmos>,
+            Self::handle::<{ $high << 4 | 0xC }, $cmos>,
+            Self::handle::<{ $high << 4 | 0xD }, $cmos>,
+            Self::handle::<{ $high << 4 | 0xE }, $cmos>,
+            Self::handle::<{ $high << 4 | 0xF }, $cmos>,
+        ]
+    };
+}
+use handlers;
+
+/// Lays out 16 rows of 16 handlers as one table.
+const fn flatten<T: Bus>(rows: [[Handler<T>; 16]; 16]) -> [Handler<T>; 256] {
+    let mut table = [rows[0][0]; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = rows[i >> 4][i & 0xF];
+        i += 1;
+    }
+    table
+}
+
+/// The bit tested or changed by the Rockwell RMB, SMB, BBR and BBS opcodes
+fn bit_of(opcode: u8) -> u8 {
+    1 << ((opcode >> 4) & 0b111)
+}
+
+impl<T: Bus> CPU<T> {
+    /// ASL: shifts left, moving bit 7 into carry.
+    fn shift_left(&mut self, value: u8) -> u8 {
+        let result = value << 1;
+        self.status.set_carry((value & 0b10000000) != 0);
+        self.status.set_zero(result == 0);
+        self.status.set_negative((result & 0b10000000) != 0);
+        result
+    }
+
+    /// LSR: shifts right, moving bit 0 into carry.
+    fn shift_right(&mut self, value: u8) -> u8 {
+        let result = value >> 1;
+        self.status.set_carry((value & 1) != 0);
+        self.status.set_zero(result == 0);
+        self.status.set_negative(false);
+        result
+    }
+
+    /// ROL: rotates left through carry.
+    fn rotate_left(&mut self, value: u8) -> u8 {
+        let result = (value << 1) | self.status.carry() as u8;
+        self.status.set_carry((value & 0b10000000) != 0);
+        self.status.set_zero(result == 0);
+        self.status.set_negative((result & 0b10000000) != 0);
+        result
+    }
+
+    /// ROR: rotates right through carry.
+    fn rotate_right(&mut self, value: u8) -> u8 {
+        let result = (value >> 1) | ((self.status.carry() as u8) << 7);
+        self.status.set_carry((value & 1) != 0);
+        self.status.set_zero(result == 0);
+        self.status.set_negative((result & 0b10000000) != 0);
+        result
+    }
+
+    /// CMP, CPX, CPY: sets the flags as if `rhs` was subtracted from `register`.
+    fn compare(&mut self, register: u8, rhs: u8) {
+        let (res, borrow) = register.borrowing_sub(rhs, false);
+        self.status.set_carry(!borrow);
+        self.status.set_zero(res == 0);
+        self.status.set_negative((res & 0b10000000) != 0);
+    }
+
+    /// ARR: ANDs with the accumulator, then rotates right, with flags (and, in decimal mode, the
+    /// result) coming from the adder as well.
+    fn and_rotate_right(&mut self, rhs: u8) {
+        let and = self.ac & rhs;
+        let carry_in = self.status.carry() as u8;
+        let mut result = (and >> 1) | (carry_in << 7);
+        if self.status.decimal() && self.variant.has_decimal_mode() {
+            self.status.set_negative(carry_in != 0);
+            self.status.set_zero(result == 0);
+            self.status.set_overflow(((and ^ result) & 0b01000000) != 0);
+            if (and & 0x0F) + (and & 0x01) > 0x05 {
+                result = (result & 0xF0) | (result.wrapping_add(0x06) & 0x0F);
+            }
+            let carry = (and & 0xF0) as u16 + (and & 0x10) as u16 > 0x50;
+            if carry {
+                result = result.wrapping_add(0x60);
+            }
+            self.status.set_carry(carry);
+        } else {
+            self.status.set_zero(result == 0);
+            self.status.set_negative((result & 0b10000000) != 0);
+            self.status.set_carry((result & 0b01000000) != 0);
+            self.status
+                .set_overflow(((result >> 6) ^ (result >> 5)) & 1 != 0);
+        }
+        self.ac = result;
+    }
+
+    /// SHA, SHX, SHY, TAS: stores `value` ANDed with the high byte of the base address plus one.
+    ///
+    /// When indexing crosses a page, the high byte of the target address gets replaced with the
+    /// stored value as well.
+    fn store_and_high(
+        &mut self,
+        value: u8,
+        operand: OperandData,
+        page_crossed: bool,
+    ) -> Result<(), ErrorKind> {
+        let target = operand.address()?;
+        let high = (target >> 8) as u8;
+        let value = value
+            & if page_crossed {
+                high
+            } else {
+                high.wrapping_add(1)
+            };
+        let addr = if page_crossed {
+            ((value as u16) << 8) | (target & 0x00FF)
+        } else {
+            target
+        };
+        self.write_operand(addr, value);
+        Ok(())
+    }
+
+    /// Jumps to the operand address if `condition` holds. Returns whether the branch was taken.
+    fn branch(&mut self, condition: bool, operand: OperandData) -> Result<bool, ErrorKind> {
+        let target = operand.address()?;
+        if condition {
+            // The next opcode is read while the offset is added to the low byte of PC,
+            // and the wrong page is read while the high byte is fixed.
+            self.read_bus(self.pc);
+            if crosses_page(self.pc, target) {
+                self.read_bus((self.pc & 0xFF00) | (target & 0x00FF));
+            }
+            self.pc = target;
+        }
+        Ok(condition)
+    }
+
+    /// ADC: adds `rhs` and the carry to the accumulator, in decimal if D is set and the
+    /// variant has decimal mode.
+    fn add_with_carry(&mut self, rhs: u8) {
+        let (binary, carry) = self.ac.carrying_add(rhs, self.status.carry());
+        if !(self.status.decimal() && self.variant.has_decimal_mode()) {
+            self.status
+                .set_overflow(((self.ac ^ binary) & (rhs ^ binary) & 0x80) != 0);
+            self.ac = binary;
+            self.status.set_carry(carry);
+            self.status.set_zero(self.ac == 0);
+            self.status.set_negative((self.ac & 0b10000000) != 0);
+            return;
+        }
+        // NMOS decimal addition. Z comes from the binary sum, N and V from the sum after the
+        // low nibble is adjusted but before the high one is. Invalid BCD digits are handled
+        // exactly as on the real chip.
+        let mut low = (self.ac & 0x0F) as u16 + (rhs & 0x0F) as u16 + self.status.carry() as u16;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let mut sum = (self.ac & 0xF0) as u16 + (rhs & 0xF0) as u16 + low;
+        self.status.set_zero(binary == 0);
+        self.status.set_negative((sum & 0x80) != 0);
+        self.status
+            .set_overflow(((self.ac as u16 ^ sum) & (rhs as u16 ^ sum) & 0x80) != 0);
+        if sum >= 0xA0 {
+            sum += 0x60;
+        }
+        self.ac = sum as u8;
+        self.status.set_carry(sum >= 0x100);
+        if self.variant.is_cmos() {
+            // The 65C02 sets N and Z from the decimal result.
+            self.status.set_zero(self.ac == 0);
+            self.status.set_negative((self.ac & 0b10000000) != 0);
+        }
+    }
+
+    /// SBC: subtracts `rhs` and the borrow (inverted carry) from the accumulator, in decimal
+    /// if D is set and the variant has decimal mode.
+    fn subtract_with_carry(&mut self, rhs: u8) {
+        let borrow_in = !self.status.carry();
+        let (binary, borrow) = self.ac.borrowing_sub(rhs, borrow_in);
+        self.status
+            .set_overflow(((self.ac ^ rhs) & 0x80 != 0) && ((self.ac ^ binary) & 0x80) != 0);
+        self.status.set_carry(!borrow);
+        self.status.set_zero(binary == 0);
+        self.status.set_negative((binary & 0b10000000) != 0);
+        if !(self.status.decimal() && self.variant.has_decimal_mode()) {
+            self.ac = binary;
+            return;
+        }
+        if self.variant.is_cmos() {
+            // 65C02 decimal subtraction. C and V are the same as for the binary subtraction,
+            // N and Z come from the decimal result.
+            let low = (self.ac & 0x0F) as i16 - (rhs & 0x0F) as i16 - borrow_in as i16;
+            let mut difference = self.ac as i16 - rhs as i16 - borrow_in as i16;
+            if difference < 0 {
+                difference -= 0x60;
+            }
+            if low < 0 {
+                difference -= 0x06;
+            }
+            self.ac = difference as u8;
+            self.status.set_zero(self.ac == 0);
+            self.status.set_negative((self.ac & 0b10000000) != 0);
+            return;
+        }
+        // NMOS decimal subtraction. All flags are the same as for the binary subtraction.
+        let mut low = (self.ac & 0x0F) as i16 - (rhs & 0x0F) as i16 - borrow_in as i16;
+        if low < 0 {
+            low = ((low - 0x06) & 0x0F) - 0x10;
+        }
+        let mut difference = (self.ac & 0xF0) as i16 - (rhs & 0xF0) as i16 + low;
+        if difference < 0 {
+            difference -= 0x60;
+        }
+        self.ac = difference as u8;
+    }
+
+    /// ADC and SBC take an extra cycle in decimal mode on the 65C02, spent reading the next
+    /// opcode. Returns whether that cycle was taken.
+    fn cmos_decimal_cycle(&mut self) -> bool {
+        let taken = self.variant.is_cmos() && self.status.decimal();
+        if taken {
+            self.read_bus(self.pc);
+        }
+        taken
+    }
+}
@@ -0,0 +1,53 @@
+use egui::{text::LayoutJob, Color32, FontFamily, FontId, TextFormat, Ui};
+
+use super::{bus::Bus, status::Status, CPU};
+
+/// Lays out `title`, the status byte and its flags, with the set ones in red.
+fn flags_layout(title: &str, status: &Status) -> LayoutJob {
+    let monospace = FontId {
+        family: FontFamily::Monospace,
+        ..FontId::default()
+    };
+    let mut layout = LayoutJob::default();
+    layout.append(
+        &format!("{title}: {:#04X} (", status.byte),
+        0.0,
+        TextFormat::default(),
+    );
+    for flag in status.flags() {
+        let format = TextFormat {
+            font_id: monospace.clone(),
+            color: if flag.set {
+                Color32::RED
+            } else {
+                Color32::DARK_GRAY
+            },
+            ..TextFormat::default()
+        };
+        layout.append(&flag.name.to_string(), 0.0, format);
+    }
+    layout.append(")", 0.0, TextFormat::default());
+    layout
+}
+
+impl Status {
+    pub fn render(&self, ui: &mut Ui) {
+        ui.label(flags_layout("Status flag", self));
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    pub fn render(&self, ui: &mut Ui) {
+        let registers = self.registers();
+        ui.label(format!(
+            "A: {:#04x} X: {:#04x} Y: {:#04x} PC: {:#06x} SP: {:#04x}",
+            registers.ac, registers.x, registers.y, registers.pc, registers.stack_pointer
+        ));
+        ui.label(flags_layout("Status register", &registers.status));
+        ui.monospace(format!("Next: {}", self.disassemble(self.pc)));
+        ui.label("Stack:");
+        for (addr, value) in self.stack_contents() {
+            ui.monospace(format!("0x{addr:04x}: 0x{value:02x}"));
+        }
+    }
+}
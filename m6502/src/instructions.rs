@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Instruction {
     /// Add with carry
     ADC,
@@ -112,10 +112,134 @@ pub enum Instruction {
     TXS,
     /// Transfer y to accumulator
     TYA,
+    /// And, then logical shift right (undocumented)
+    ALR,
+    /// And, then copy bit 7 to carry (undocumented)
+    ANC,
+    /// And, then rotate right (undocumented)
+    ARR,
+    /// And x with accumulator, then subtract without borrow into x (undocumented)
+    AXS,
+    /// Decrement, then compare (undocumented)
+    DCP,
+    /// Increment, then subtract with carry (undocumented)
+    ISC,
+    /// Halt the processor (undocumented)
+    JAM,
+    /// And with stack pointer into accumulator, x and stack pointer (undocumented)
+    LAS,
+    /// Load accumulator and x (undocumented)
+    LAX,
+    /// And with accumulator or magic constant, load into accumulator and x (undocumented, unstable)
+    LXA,
+    /// Rotate left, then and (undocumented)
+    RLA,
+    /// Rotate right, then add with carry (undocumented)
+    RRA,
+    /// Store accumulator and x (undocumented)
+    SAX,
+    /// Store accumulator and x and high address byte + 1 (undocumented, unstable)
+    SHA,
+    /// Store x and high address byte + 1 (undocumented, unstable)
+    SHX,
+    /// Store y and high address byte + 1 (undocumented, unstable)
+    SHY,
+    /// Arithmetic shift left, then or (undocumented)
+    SLO,
+    /// Logical shift right, then exclusive or (undocumented)
+    SRE,
+    /// Transfer accumulator and x to stack pointer, then store it and high address byte + 1 (undocumented, unstable)
+    TAS,
+    /// And x and accumulator or magic constant (undocumented, unstable)
+    XAA,
+    /// Branch always (65C02)
+    BRA,
+    /// Push x (65C02)
+    PHX,
+    /// Push y (65C02)
+    PHY,
+    /// Pull x (65C02)
+    PLX,
+    /// Pull y (65C02)
+    PLY,
+    /// Stop the processor until reset (65C02)
+    STP,
+    /// Store zero (65C02)
+    STZ,
+    /// Test and reset bits with accumulator (65C02)
+    TRB,
+    /// Test and set bits with accumulator (65C02)
+    TSB,
+    /// Wait for interrupt (65C02)
+    WAI,
+    /// Reset memory bit 0 (rockwell 65C02)
+    RMB0,
+    /// Reset memory bit 1 (rockwell 65C02)
+    RMB1,
+    /// Reset memory bit 2 (rockwell 65C02)
+    RMB2,
+    /// Reset memory bit 3 (rockwell 65C02)
+    RMB3,
+    /// Reset memory bit 4 (rockwell 65C02)
+    RMB4,
+    /// Reset memory bit 5 (rockwell 65C02)
+    RMB5,
+    /// Reset memory bit 6 (rockwell 65C02)
+    RMB6,
+    /// Reset memory bit 7 (rockwell 65C02)
+    RMB7,
+    /// Set memory bit 0 (rockwell 65C02)
+    SMB0,
+    /// Set memory bit 1 (rockwell 65C02)
+    SMB1,
+    /// Set memory bit 2 (rockwell 65C02)
+    SMB2,
+    /// Set memory bit 3 (rockwell 65C02)
+    SMB3,
+    /// Set memory bit 4 (rockwell 65C02)
+    SMB4,
+    /// Set memory bit 5 (rockwell 65C02)
+    SMB5,
+    /// Set memory bit 6 (rockwell 65C02)
+    SMB6,
+    /// Set memory bit 7 (rockwell 65C02)
+    SMB7,
+    /// Branch on memory bit 0 reset (rockwell 65C02)
+    BBR0,
+    /// Branch on memory bit 1 reset (rockwell 65C02)
+    BBR1,
+    /// Branch on memory bit 2 reset (rockwell 65C02)
+    BBR2,
+    /// Branch on memory bit 3 reset (rockwell 65C02)
+    BBR3,
+    /// Branch on memory bit 4 reset (rockwell 65C02)
+    BBR4,
+    /// Branch on memory bit 5 reset (rockwell 65C02)
+    BBR5,
+    /// Branch on memory bit 6 reset (rockwell 65C02)
+    BBR6,
+    /// Branch on memory bit 7 reset (rockwell 65C02)
+    BBR7,
+    /// Branch on memory bit 0 set (rockwell 65C02)
+    BBS0,
+    /// Branch on memory bit 1 set (rockwell 65C02)
+    BBS1,
+    /// Branch on memory bit 2 set (rockwell 65C02)
+    BBS2,
+    /// Branch on memory bit 3 set (rockwell 65C02)
+    BBS3,
+    /// Branch on memory bit 4 set (rockwell 65C02)
+    BBS4,
+    /// Branch on memory bit 5 set (rockwell 65C02)
+    BBS5,
+    /// Branch on memory bit 6 set (rockwell 65C02)
+    BBS6,
+    /// Branch on memory bit 7 set (rockwell 65C02)
+    BBS7,
 }
 
-impl std::fmt::Display for Instruction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         use Instruction::*;
         f.write_str(match *self {
             ADC => "Add with carry",
@@ -174,6 +298,68 @@ impl std::fmt::Display for Instruction {
             TXA => "Transfer x to accumulator",
             TXS => "Transfer x to stack pointer",
             TYA => "Transfer y to accumulator",
+            ALR => "And, then logical shift right (undocumented)",
+            ANC => "And, then copy bit 7 to carry (undocumented)",
+            ARR => "And, then rotate right (undocumented)",
+            AXS => "And x with accumulator, then subtract without borrow into x (undocumented)",
+            DCP => "Decrement, then compare (undocumented)",
+            ISC => "Increment, then subtract with carry (undocumented)",
+            JAM => "Halt the processor (undocumented)",
+            LAS => "And with stack pointer into accumulator, x and stack pointer (undocumented)",
+            LAX => "Load accumulator and x (undocumented)",
+            LXA => "And with accumulator or magic constant, load into accumulator and x (undocumented, unstable)",
+            RLA => "Rotate left, then and (undocumented)",
+            RRA => "Rotate right, then add with carry (undocumented)",
+            SAX => "Store accumulator and x (undocumented)",
+            SHA => "Store accumulator and x and high address byte + 1 (undocumented, unstable)",
+            SHX => "Store x and high address byte + 1 (undocumented, unstable)",
+            SHY => "Store y and high address byte + 1 (undocumented, unstable)",
+            SLO => "Arithmetic shift left, then or (undocumented)",
+            SRE => "Logical shift right, then exclusive or (undocumented)",
+            TAS => "Transfer accumulator and x to stack pointer, then store it and high address byte + 1 (undocumented, unstable)",
+            XAA => "And x and accumulator or magic constant (undocumented, unstable)",
+            BRA => "Branch always (65C02)",
+            PHX => "Push x (65C02)",
+            PHY => "Push y (65C02)",
+            PLX => "Pull x (65C02)",
+            PLY => "Pull y (65C02)",
+            STP => "Stop the processor until reset (65C02)",
+            STZ => "Store zero (65C02)",
+            TRB => "Test and reset bits with accumulator (65C02)",
+            TSB => "Test and set bits with accumulator (65C02)",
+            WAI => "Wait for interrupt (65C02)",
+            RMB0 => "Reset memory bit 0 (rockwell 65C02)",
+            RMB1 => "Reset memory bit 1 (rockwell 65C02)",
+            RMB2 => "Reset memory bit 2 (rockwell 65C02)",
+            RMB3 => "Reset memory bit 3 (rockwell 65C02)",
+            RMB4 => "Reset memory bit 4 (rockwell 65C02)",
+            RMB5 => "Reset memory bit 5 (rockwell 65C02)",
+            RMB6 => "Reset memory bit 6 (rockwell 65C02)",
+            RMB7 => "Reset memory bit 7 (rockwell 65C02)",
+            SMB0 => "Set memory bit 0 (rockwell 65C02)",
+            SMB1 => "Set memory bit 1 (rockwell 65C02)",
+            SMB2 => "Set memory bit 2 (rockwell 65C02)",
+            SMB3 => "Set memory bit 3 (rockwell 65C02)",
+            SMB4 => "Set memory bit 4 (rockwell 65C02)",
+            SMB5 => "Set memory bit 5 (rockwell 65C02)",
+            SMB6 => "Set memory bit 6 (rockwell 65C02)",
+            SMB7 => "Set memory bit 7 (rockwell 65C02)",
+            BBR0 => "Branch on memory bit 0 reset (rockwell 65C02)",
+            BBR1 => "Branch on memory bit 1 reset (rockwell 65C02)",
+            BBR2 => "Branch on memory bit 2 reset (rockwell 65C02)",
+            BBR3 => "Branch on memory bit 3 reset (rockwell 65C02)",
+            BBR4 => "Branch on memory bit 4 reset (rockwell 65C02)",
+            BBR5 => "Branch on memory bit 5 reset (rockwell 65C02)",
+            BBR6 => "Branch on memory bit 6 reset (rockwell 65C02)",
+            BBR7 => "Branch on memory bit 7 reset (rockwell 65C02)",
+            BBS0 => "Branch on memory bit 0 set (rockwell 65C02)",
+            BBS1 => "Branch on memory bit 1 set (rockwell 65C02)",
+            BBS2 => "Branch on memory bit 2 set (rockwell 65C02)",
+            BBS3 => "Branch on memory bit 3 set (rockwell 65C02)",
+            BBS4 => "Branch on memory bit 4 set (rockwell 65C02)",
+            BBS5 => "Branch on memory bit 5 set (rockwell 65C02)",
+            BBS6 => "Branch on memory bit 6 set (rockwell 65C02)",
+            BBS7 => "Branch on memory bit 7 set (rockwell 65C02)",
         })
     }
 }
@@ -0,0 +1,113 @@
+use super::{bus::Bus, CPU};
+
+/// Address of the NMI vector
+pub const NMI_VECTOR: u16 = 0xFFFA;
+/// Address of the RESET vector
+pub const RESET_VECTOR: u16 = 0xFFFC;
+/// Address of the IRQ/BRK vector
+pub const IRQ_VECTOR: u16 = 0xFFFE;
+
+/// Cycles taken by the RESET, NMI and IRQ sequences
+pub const INTERRUPT_CYCLES: u8 = 7;
+
+impl<T: Bus> CPU<T> {
+    /// Performs the RESET sequence.
+    ///
+    /// Like the real chip, the stack pointer is decremented by 3 without writing anything,
+    /// interrupts get disabled and PC is loaded from the RESET vector at 0xFFFC. This is also
+    /// the only way out of a halt.
+    /// Other registers are left as they were.
+    pub fn reset(&mut self) {
+        self.stack_pointer = self.stack_pointer.wrapping_sub(3);
+        self.status.set_interrupt_disabled(true);
+        self.nmi_pending = false;
+        self.halted = None;
+        self.waiting = false;
+        self.replay = Default::default();
+        self.pc = self.read_vector(RESET_VECTOR);
+        self.cycles_left = INTERRUPT_CYCLES;
+        self.total_cycles += INTERRUPT_CYCLES as u64;
+    }
+
+    /// Sets the level of the NMI line.
+    ///
+    /// NMI is edge-triggered: an interrupt is requested when the line goes from inactive to
+    /// active, and holding it active doesn't request another one.
+    pub fn set_nmi(&mut self, active: bool) {
+        if active && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.nmi_line = active;
+    }
+
+    /// Sets the level of the IRQ line.
+    ///
+    /// IRQ is level-triggered: an interrupt is served before every instruction for as long
+    /// as the line is active and the interrupt disable flag is clear.
+    pub fn set_irq(&mut self, active: bool) {
+        self.irq_line = active;
+    }
+
+    /// Serves a pending NMI or IRQ, if there is one.
+    ///
+    /// Returns `true` if an interrupt sequence was performed instead of an instruction.
+    pub fn poll_interrupts(&mut self) -> bool {
+        match self.take_interrupt() {
+            Some(vector) => {
+                self.profile_interrupt(vector);
+                self.interrupt(vector);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the vector of the interrupt to serve before the next instruction, if any,
+    /// acknowledging a pending NMI.
+    pub(crate) fn take_interrupt(&mut self) -> Option<u16> {
+        if self.nmi_pending {
+            self.nmi_pending = false;
+            Some(NMI_VECTOR)
+        } else if self.irq_line && !self.status.interrupt_disabled() {
+            Some(IRQ_VECTOR)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the CPU is still waiting after a WAI. NMI and IRQ wake it up, even with
+    /// interrupts disabled, in which case execution simply continues after the WAI.
+    pub(crate) fn still_waiting(&mut self) -> bool {
+        if self.nmi_pending || self.irq_line {
+            self.waiting = false;
+        }
+        self.waiting
+    }
+
+    /// Pushes PC and the status register (with B clear) and jumps through `vector`.
+    ///
+    /// The 65C02 also clears the decimal flag.
+    pub(crate) fn interrupt(&mut self, vector: u16) {
+        // The opcode fetch is suppressed, and the next byte is read and discarded.
+        self.read_bus(self.pc);
+        self.read_bus(self.pc);
+        self.push_word(self.pc);
+        self.push_byte((self.status.byte | 0b00100000) & !0b00010000);
+        self.status.set_interrupt_disabled(true);
+        if self.variant.is_cmos() {
+            self.status.set_decimal(false);
+        }
+        self.pc = self.read_vector(vector);
+        self.cycles_left = INTERRUPT_CYCLES;
+        self.total_cycles += INTERRUPT_CYCLES as u64;
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    /// Reads a 16-bit vector, low byte first.
+    pub(crate) fn read_vector(&mut self, vector: u16) -> u16 {
+        let low = self.read_bus(vector) as u16;
+        let high = self.read_bus(vector.wrapping_add(1)) as u16;
+        low | (high << 8)
+    }
+}
@@ -1,25 +1,63 @@
-#![feature(bigint_helper_methods)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use egui::{text::LayoutJob, TextFormat, Ui};
+extern crate alloc;
+
+use core::fmt;
+
+use breakpoints::{Break, BreakpointKind, Breakpoints};
+use cdl::CodeDataLogger;
+use cycle_stepping::Replay;
+use disassembler::Disassembly;
+use profiler::Profiler;
+use registers::Registers;
 use status::Status;
 use thiserror::Error;
+use trace::Tracer;
+use variant::Variant;
 
-use self::{addressing_modes::OperandData, bus::Bus, instructions::Instruction};
+use self::{
+    addressing_modes::OperandData,
+    bus::{Bus, UnmappedAccess},
+    instructions::Instruction,
+};
 
 /// Addressing modes and how the CPU uses them
 pub mod addressing_modes;
+/// Stopping execution at breakpoints and watchpoints
+pub mod breakpoints;
 /// Memory bus
 pub mod bus;
+/// Logging which bytes are code and which are data
+pub mod cdl;
+/// Executing one bus access per tick
+pub mod cycle_stepping;
+/// Turning machine code back into assembly
+pub mod disassembler;
 /// Main instruction logic
 pub mod execution;
+/// Drawing the CPU state with egui
+#[cfg(feature = "egui")]
+pub mod gui;
 /// A list of instructions
 pub mod instructions;
-/// Opcode table generation and parsing
+/// Hardware interrupts: RESET, NMI and IRQ
+pub mod interrupts;
+/// The opcode tables, and what each opcode does
 pub mod opcode_table;
+/// Counting where the cycles go
+pub mod profiler;
+/// Register snapshots for frontends
+pub mod registers;
+/// Saving and restoring the whole machine state
+pub mod snapshot;
 /// Functions forking with stack.
 pub mod stack;
 /// Status register
 pub mod status;
+/// Recording executed instructions
+pub mod trace;
+/// Supported chip variants
+pub mod variant;
 
 /// A 6502 CPU
 #[derive(Clone)]
@@ -36,11 +74,53 @@ pub struct CPU<T: Bus> {
     pub status: Status,
     /// Stack pointer
     ///
-    /// The stack is LIFO, top-down, 8 bit range, 0x0100 - 0x01FF.
+    /// The stack is LIFO, top-down, 8 bit range, 0x0100 - 0x01FF. The pointer addresses the
+    /// next free byte and wraps around within page 1.
     pub stack_pointer: u8,
     pub bus: T,
+    /// The last value read or written, which unmapped addresses read back as
+    pub data_bus: u8,
     /// Cycles left until next command
     pub cycles_left: u8,
+    /// Total number of cycles taken by the instructions and interrupts executed so far
+    pub total_cycles: u64,
+    /// Current level of the NMI line
+    pub nmi_line: bool,
+    /// Set on the inactive-to-active edge of the NMI line, cleared once the NMI is served
+    pub nmi_pending: bool,
+    /// Current level of the IRQ line
+    pub irq_line: bool,
+    /// The chip being emulated
+    pub variant: Variant,
+    /// Whether the undocumented NMOS opcodes are executed. When disabled, they fail with
+    /// [`ErrorKind::NoInstruction`].
+    pub undocumented_opcodes: bool,
+    /// Why the program stopped, if it did. A halted CPU doesn't fetch anything until it's
+    /// reset.
+    pub halted: Option<Halt>,
+    /// Whether an instruction that jumps or branches to itself halts the CPU with
+    /// [`Halt::Trap`], the way test programs report they're done
+    pub trap_self_jumps: bool,
+    /// Set by WAI on the 65C02. A waiting CPU doesn't fetch anything until an interrupt is
+    /// requested.
+    pub waiting: bool,
+    /// Whether `tick` performs a single bus access, like the real chip does every cycle,
+    /// rather than running a whole instruction on its first cycle and idling for the rest
+    pub cycle_stepped: bool,
+    /// Progress through the instruction being executed when cycle-stepped
+    pub(crate) replay: Replay,
+    /// Records every instruction before it runs
+    pub tracer: Option<Tracer>,
+    /// Where execution stops, see [`execute`](CPU::execute) and [`tick`](CPU::tick)
+    pub breakpoints: Breakpoints,
+    /// Marks every byte executed or read as code or data
+    pub code_data_logger: Option<CodeDataLogger>,
+    /// Counts executions and cycles per address and per subroutine
+    pub profiler: Option<Profiler>,
+    /// Whether bus accesses go through the replay, the watchpoints and the code/data logger.
+    /// Only [`run_cycles`](CPU::run_cycles) clears it, around instructions run with none of
+    /// them in use.
+    pub(crate) hooked: bool,
 }
 
 impl<T> CPU<T>
@@ -48,6 +128,10 @@ where
     T: Bus,
 {
     pub fn new(bus: T) -> Self {
+        Self::with_variant(bus, Variant::default())
+    }
+
+    pub fn with_variant(bus: T, variant: Variant) -> Self {
         Self {
             pc: 0,
             ac: 0,
@@ -56,78 +140,211 @@ where
             status: Status::default(),
             stack_pointer: 0,
             bus,
+            data_bus: 0,
             cycles_left: 0,
+            total_cycles: 0,
+            nmi_line: false,
+            nmi_pending: false,
+            irq_line: false,
+            variant,
+            undocumented_opcodes: false,
+            halted: None,
+            trap_self_jumps: false,
+            waiting: false,
+            cycle_stepped: false,
+            replay: Replay::default(),
+            tracer: None,
+            breakpoints: Breakpoints::default(),
+            code_data_logger: None,
+            profiler: None,
+            hooked: true,
         }
     }
 }
 impl<T: Bus> CPU<T> {
+    #[inline(always)]
     pub fn fetch_byte(&mut self) -> u8 {
-        let r = self.bus.read(self.pc as usize);
-        self.pc += 1;
+        self.log_code_data(self.pc, cdl::CODE | cdl::OPERAND);
+        let r = self.read_bus_watched(self.pc, false);
+        self.pc = self.pc.wrapping_add(1);
         r
     }
 
-    pub fn fetch_word(&mut self) -> u16 {
-        let r = self.bus.read_word(self.pc as usize);
-        self.pc += 2;
+    /// Fetches the opcode of the next instruction.
+    #[inline(always)]
+    pub(crate) fn fetch_opcode(&mut self) -> u8 {
+        self.log_opcode(self.pc);
+        let r = self.read_bus_watched(self.pc, false);
+        self.pc = self.pc.wrapping_add(1);
         r
     }
 
-    pub fn tick(&mut self) -> Result<(), CPUError> {
+    #[inline(always)]
+    pub fn fetch_word(&mut self) -> u16 {
+        let low = self.fetch_byte() as u16;
+        let high = self.fetch_byte() as u16;
+        low | (high << 8)
+    }
+
+    /// Runs one cycle. Returns the breakpoint that stopped the CPU on this cycle, if any; an
+    /// execute breakpoint stops it before the instruction's first cycle, without spending it.
+    pub fn tick(&mut self) -> Result<Option<Break>, CPUError> {
+        if self.cycle_stepped {
+            let result = self.tick_cycle();
+            let hit = self.breakpoints.take_hit();
+            return result.map(|_| hit);
+        }
+        let mut hit = None;
         if self.cycles_left == 0 {
-            self.execute()?;
+            hit = self.execute()?;
+            if hit.is_some_and(|hit| hit.kind == BreakpointKind::Execute) {
+                return Ok(hit);
+            }
         }
         self.cycles_left -= 1;
         self.bus.tick();
-        Ok(())
+        Ok(hit)
     }
 
-    pub fn render(&mut self, ui: &mut Ui) {
-        ui.label(&format!(
-            "A: 0x{:02x} X: 0x{:02x} Y: 0x{:02} PC: 0x{:04x} SP: 0x{:02x}",
-            self.ac, self.x, self.y, self.pc, self.stack_pointer
-        ));
-        ui.label({
-            fn style_flag(flag: bool) -> TextFormat {
-                TextFormat {
-                    color: if flag {
-                        egui::Color32::RED
-                    } else {
-                        egui::Color32::DARK_GRAY
-                    },
-                    ..Default::default()
+    /// Runs `cycles` cycles, like as many calls to [`tick`](CPU::tick) would, but a whole
+    /// instruction at a time. An instruction that doesn't fit is started, and its last cycles
+    /// are left for the next call.
+    ///
+    /// Stops early at a breakpoint, which it returns like `tick` does.
+    ///
+    /// This is the fast way to run the CPU: without a tracer, a profiler, breakpoints, a code/data
+    /// logger or debug logging, the instructions run back to back, with none of the checks
+    /// [`execute`](CPU::execute) makes before each of them or on each bus access.
+    pub fn run_cycles(&mut self, cycles: u64) -> Result<Option<Break>, CPUError> {
+        let mut left = cycles;
+        if self.cycle_stepped {
+            while left > 0 {
+                left -= 1;
+                if let Some(hit) = self.tick()? {
+                    return Ok(Some(hit));
                 }
             }
-            let mut layout = LayoutJob::default();
-            layout.append(
-                &format!("Status register: 0x{:02x} (", self.status.byte),
-                0.0,
-                TextFormat::default(),
-            );
-            layout.append("N", 0.0, style_flag(self.status.negative()));
-            layout.append("V", 0.0, style_flag(self.status.overflow()));
-            layout.append("_", 0.0, style_flag(self.status._ignored_flag()));
-            layout.append("B", 0.0, style_flag(self.status.break_flag()));
-            layout.append("D", 0.0, style_flag(self.status.decimal()));
-            layout.append("I", 0.0, style_flag(self.status.interrupt_disabled()));
-            layout.append("Z", 0.0, style_flag(self.status.zero()));
-            layout.append("C", 0.0, style_flag(self.status.carry()));
-            layout.append(")", 0.0, TextFormat::default());
-            layout
-        });
-        ui.label("Stack:");
-        for i in 0..(self.stack_pointer / 2) as usize {
-            ui.label(&format!(
-                "0x{:04x}",
-                self.bus
-                    .read_word(0x0100 + self.stack_pointer as usize - 2 - i * 2)
-            ));
+            return Ok(None);
+        }
+        let instrumented = self.tracer.is_some()
+            || self.profiler.is_some()
+            || self.breakpoints.is_active()
+            || self.code_data_logger.is_some()
+            || debug_logging();
+        loop {
+            let idle = left.min(self.cycles_left as u64);
+            self.cycles_left -= idle as u8;
+            left -= idle;
+            for _ in 0..idle {
+                self.bus.tick();
+            }
+            if left == 0 {
+                return Ok(None);
+            }
+            if !instrumented
+                && !(self.halted.is_some() || self.waiting || self.nmi_pending || self.irq_line)
+            {
+                self.hooked = false;
+                let result = self.execute_instruction();
+                self.hooked = true;
+                result?;
+                continue;
+            }
+            if let Some(hit) = self.execute()? {
+                // Like `tick`, stop after the first cycle of the instruction that hit a read
+                // or write breakpoint, and before the one that hit an execute breakpoint.
+                if hit.kind != BreakpointKind::Execute {
+                    self.cycles_left -= 1;
+                    self.bus.tick();
+                }
+                return Ok(Some(hit));
+            }
+        }
+    }
+}
+
+/// Whether executed instructions are logged
+#[cfg(feature = "log")]
+fn debug_logging() -> bool {
+    log::log_enabled!(log::Level::Debug)
+}
+
+#[cfg(not(feature = "log"))]
+fn debug_logging() -> bool {
+    false
+}
+
+/// Why the CPU stopped running the program. Unlike a [`CPUError`], this is something the
+/// program did on purpose, or at least something the real chip does too.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Halt {
+    /// A JAM opcode, or STP on the 65C02, ran at `pc`
+    Jam { pc: u16, opcode: u8 },
+    /// The instruction at `pc` jumped or branched to itself, see [`CPU::trap_self_jumps`]
+    Trap { pc: u16 },
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Halt::Jam { pc, opcode } => {
+                write!(f, "opcode ${opcode:02X} at ${pc:04X} jammed the CPU")
+            }
+            Halt::Trap { pc } => write!(f, "trapped at ${pc:04X}"),
+        }
+    }
+}
+
+/// A failure of the emulator, with the state of the CPU when it happened
+#[derive(Error, Debug)]
+pub struct CPUError {
+    /// Address of the instruction that failed, or of the one an interrupt sequence that failed
+    /// came before
+    pub pc: u16,
+    /// The instruction at `pc`
+    pub instruction: Disassembly,
+    /// The registers when it failed, part way through the instruction. The cycle count is the
+    /// one the instruction started at.
+    pub registers: Registers,
+    pub kind: ErrorKind,
+}
+
+/// Shows the error, then where it happened, like
+/// `No instruction implemented for opcode 0x02. At $0600 (.byte $02), PC:0601 A:00 ...`.
+impl fmt::Display for CPUError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} At ${:04X} ({}), {}, cycle {}",
+            self.kind, self.pc, self.instruction, self.registers, self.registers.total_cycles
+        )
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    /// Adds the state of the CPU to an error of the instruction at `pc`.
+    #[cold]
+    pub(crate) fn error(&self, pc: u16, kind: ErrorKind) -> CPUError {
+        CPUError {
+            pc,
+            instruction: self.disassemble(pc),
+            registers: self.registers(),
+            kind,
+        }
+    }
+
+    /// Fails with the access the bus refused since the last check, if any.
+    #[inline]
+    pub(crate) fn bus_fault(&mut self) -> Result<(), ErrorKind> {
+        match self.bus.take_fault() {
+            Some(access) => Err(ErrorKind::Unmapped(access)),
+            None => Ok(()),
         }
     }
 }
 
 #[derive(Error, Debug)]
-pub enum CPUError {
+pub enum ErrorKind {
     #[error("No instruction implemented for opcode {0:#04X}.")]
     NoInstruction(u8),
     #[error("Instruction {0:?} unimplemented.")]
@@ -138,4 +355,6 @@ pub enum CPUError {
     OperandNotAddress(OperandData),
     #[error("Operand {0:?} can't be written to.")]
     OperandNotWriteable(OperandData),
+    #[error("{0}")]
+    Unmapped(UnmappedAccess),
 }
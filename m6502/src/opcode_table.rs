@@ -1,5 +1,7 @@
-use crate::addressing_modes::AddressingMode;
+use crate::addressing_modes::{Access, AddressingMode};
 use crate::instructions::Instruction;
+use crate::status::Flags;
+use crate::variant::Variant;
 
 /// A rule by which the final cycle count of an instruction is determined
 #[derive(Copy, Clone, Debug)]
@@ -13,6 +15,19 @@ pub enum CycleRule {
     AddOneTwo,
 }
 
+impl CycleRule {
+    /// Cycles to add to the baseline count, given whether the effective address (or branch
+    /// target) is on another page and whether a branch was taken.
+    pub fn extra_cycles(self, page_crossed: bool, branch_taken: bool) -> u8 {
+        match self {
+            CycleRule::None => 0,
+            CycleRule::AddOnCross => page_crossed as u8,
+            CycleRule::AddOneTwo if branch_taken => 1 + page_crossed as u8,
+            CycleRule::AddOneTwo => 0,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct OpcodeEntry {
     /// The byte corresponding to the instruction
@@ -25,8 +40,16 @@ pub struct OpcodeEntry {
     pub bytes: u8,
     /// Baseline cycle count
     pub cycles: u8,
-    /// A rule by which the final cycle count is determinef
+    /// A rule by which the final cycle count is determined
     pub cycle_rule: CycleRule,
+    /// Flags the result depends on
+    pub flags_read: Flags,
+    /// Flags the instruction may change
+    pub flags_written: Flags,
+    /// How the instruction uses memory and the PC
+    pub access: Access,
+    /// Whether the opcode is in the manufacturer's documentation
+    pub official: bool,
 }
 
 impl OpcodeEntry {
@@ -45,18 +68,113 @@ impl OpcodeEntry {
             bytes,
             cycles,
             cycle_rule,
+            flags_read: flags_read(instruction),
+            flags_written: flags_written(instruction, addressing_mode),
+            access: Access::of(instruction),
+            official: true,
         }
     }
 }
 
-lazy_static::lazy_static! {
-    pub static ref OPCODE_TABLE: [Option<OpcodeEntry>; 256] = {
-        let mut x = [None; 256];
-        for i in OPCODE_LIST {
-            x[i.code as usize] = Some(*i);
-        }
-        x
-    };
+/// The flags an instruction depends on
+const fn flags_read(instruction: Instruction) -> Flags {
+    use Instruction::*;
+    match instruction {
+        ADC | SBC | ISC | RRA | ARR => Flags::CARRY.union(Flags::DECIMAL),
+        ROL | ROR | RLA | BCC | BCS => Flags::CARRY,
+        BEQ | BNE => Flags::ZERO,
+        BMI | BPL => Flags::NEGATIVE,
+        BVC | BVS => Flags::OVERFLOW,
+        // The whole register is pushed.
+        PHP | BRK => Flags::ALL,
+        _ => Flags::NONE,
+    }
+}
+
+/// The flags an instruction may change
+const fn flags_written(instruction: Instruction, addressing_mode: AddressingMode) -> Flags {
+    use Instruction::*;
+    const NZ: Flags = Flags::NEGATIVE.union(Flags::ZERO);
+    const NZC: Flags = NZ.union(Flags::CARRY);
+    const NVZC: Flags = NZC.union(Flags::OVERFLOW);
+    match instruction {
+        ADC | SBC | ISC | RRA | ARR => NVZC,
+        ASL | LSR | ROL | ROR | SLO | SRE | RLA | CMP | CPX | CPY | DCP | ANC | ALR | AXS => NZC,
+        AND | ORA | EOR | LDA | LDX | LDY | LAX | LXA | XAA | LAS | TAX | TAY | TSX | TXA | TYA
+        | PLA | PLX | PLY | INC | INX | INY | DEC | DEX | DEY => NZ,
+        // BIT #imm of the 65C02 has no memory bits 6 and 7 to copy.
+        BIT if matches!(addressing_mode, AddressingMode::Immediate) => Flags::ZERO,
+        BIT => NZ.union(Flags::OVERFLOW),
+        TRB | TSB => Flags::ZERO,
+        CLC | SEC => Flags::CARRY,
+        CLD | SED => Flags::DECIMAL,
+        CLI | SEI => Flags::INTERRUPT_DISABLE,
+        CLV => Flags::OVERFLOW,
+        PLP | RTI => Flags::ALL,
+        BRK => Flags::INTERRUPT_DISABLE,
+        _ => Flags::NONE,
+    }
+}
+
+/// Documented opcodes of the NMOS 6502
+pub const OPCODE_TABLE: [Option<OpcodeEntry>; 256] = fill([None; 256], OPCODE_LIST, true);
+/// Undocumented NMOS opcodes, filling the holes of [`OPCODE_TABLE`]
+pub const UNDOCUMENTED_OPCODE_TABLE: [Option<OpcodeEntry>; 256] =
+    fill([None; 256], UNDOCUMENTED_OPCODE_LIST, false);
+/// Opcodes of the 65C02
+pub const CMOS_OPCODE_TABLE: [Option<OpcodeEntry>; 256] = cmos_opcode_table();
+
+/// Puts every entry of `list` in `table`, at its opcode.
+const fn fill(
+    mut table: [Option<OpcodeEntry>; 256],
+    list: &[OpcodeEntry],
+    official: bool,
+) -> [Option<OpcodeEntry>; 256] {
+    let mut i = 0;
+    while i < list.len() {
+        let mut entry = list[i];
+        entry.official = official;
+        table[entry.code as usize] = Some(entry);
+        i += 1;
+    }
+    table
+}
+
+const fn cmos_opcode_table() -> [Option<OpcodeEntry>; 256] {
+    let mut table = fill(OPCODE_TABLE, CMOS_OPCODE_LIST, true);
+    table = fill(table, CMOS_UNDOCUMENTED_OPCODE_LIST, false);
+    // BRK also clears the decimal flag on the 65C02.
+    if let Some(brk) = &mut table[0x00] {
+        brk.flags_written = brk.flags_written.union(Flags::DECIMAL);
+    }
+    table
+}
+
+/// Looks up an opcode in the tables of a variant. The undocumented NMOS opcodes are only
+/// included if `undocumented_opcodes` is set; those of the 65C02 always are.
+pub fn decode(variant: Variant, undocumented_opcodes: bool, opcode: u8) -> Option<OpcodeEntry> {
+    match variant {
+        Variant::Cmos65C02 => CMOS_OPCODE_TABLE[opcode as usize],
+        Variant::Nmos | Variant::Ricoh2A03 => OPCODE_TABLE[opcode as usize]
+            .or(UNDOCUMENTED_OPCODE_TABLE[opcode as usize].filter(|_| undocumented_opcodes)),
+    }
+}
+
+/// Finds the opcode of an instruction in an addressing mode, the reverse of [`decode`].
+///
+/// Where several opcodes do the same, the official one is chosen, then the lowest.
+pub fn encode(
+    variant: Variant,
+    undocumented_opcodes: bool,
+    instruction: Instruction,
+    addressing_mode: AddressingMode,
+) -> Option<OpcodeEntry> {
+    (0..=255)
+        .filter_map(|opcode| decode(variant, undocumented_opcodes, opcode))
+        .filter(|entry| {
+            entry.instruction == instruction && entry.addressing_mode == addressing_mode
+        })
+        .min_by_key(|entry| (!entry.official, entry.code))
 }
 
 /// An opcode list
@@ -113,6 +231,8 @@ const OPCODE_LIST: &[OpcodeEntry] = &[
     OpcodeEntry::new(0xD8, Instruction::CLD, AddressingMode::Implied, 1, 2, CycleRule::None),
     // CLI - Clear Interrupt Disable
     OpcodeEntry::new(0x58, Instruction::CLI, AddressingMode::Implied, 1, 2, CycleRule::None),
+    // CLV - Clear Overflow Flag
+    OpcodeEntry::new(0xB8, Instruction::CLV, AddressingMode::Implied, 1, 2, CycleRule::None),
     // CMP - Compare Memory with Accumulator
     OpcodeEntry::new(0xC9, Instruction::CMP, AddressingMode::Immediate, 2, 2, CycleRule::None),
     OpcodeEntry::new(0xC5, Instruction::CMP, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
@@ -268,3 +388,273 @@ const OPCODE_LIST: &[OpcodeEntry] = &[
     // TXA - Transfer Y to Accumulator
     OpcodeEntry::new(0x98, Instruction::TYA, AddressingMode::Implied, 1, 2, CycleRule::None),
 ];
+
+/// Undocumented opcodes of the NMOS 6502, executed when enabled with
+/// [`CPU::undocumented_opcodes`](crate::CPU::undocumented_opcodes)
+#[rustfmt::skip]
+const UNDOCUMENTED_OPCODE_LIST: &[OpcodeEntry] = &[
+    // SLO - Shift left, then OR with Accumulator
+    OpcodeEntry::new(0x07, Instruction::SLO, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x17, Instruction::SLO, AddressingMode::ZeroPageX, 2, 6, CycleRule::None),
+    OpcodeEntry::new(0x0F, Instruction::SLO, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    OpcodeEntry::new(0x1F, Instruction::SLO, AddressingMode::AbsoluteX, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x1B, Instruction::SLO, AddressingMode::AbsoluteY, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x03, Instruction::SLO, AddressingMode::IndirectX, 2, 8, CycleRule::None),
+    OpcodeEntry::new(0x13, Instruction::SLO, AddressingMode::IndirectY, 2, 8, CycleRule::None),
+    // RLA - Rotate left, then AND with Accumulator
+    OpcodeEntry::new(0x27, Instruction::RLA, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x37, Instruction::RLA, AddressingMode::ZeroPageX, 2, 6, CycleRule::None),
+    OpcodeEntry::new(0x2F, Instruction::RLA, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    OpcodeEntry::new(0x3F, Instruction::RLA, AddressingMode::AbsoluteX, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x3B, Instruction::RLA, AddressingMode::AbsoluteY, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x23, Instruction::RLA, AddressingMode::IndirectX, 2, 8, CycleRule::None),
+    OpcodeEntry::new(0x33, Instruction::RLA, AddressingMode::IndirectY, 2, 8, CycleRule::None),
+    // SRE - Shift right, then EOR with Accumulator
+    OpcodeEntry::new(0x47, Instruction::SRE, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x57, Instruction::SRE, AddressingMode::ZeroPageX, 2, 6, CycleRule::None),
+    OpcodeEntry::new(0x4F, Instruction::SRE, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    OpcodeEntry::new(0x5F, Instruction::SRE, AddressingMode::AbsoluteX, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x5B, Instruction::SRE, AddressingMode::AbsoluteY, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x43, Instruction::SRE, AddressingMode::IndirectX, 2, 8, CycleRule::None),
+    OpcodeEntry::new(0x53, Instruction::SRE, AddressingMode::IndirectY, 2, 8, CycleRule::None),
+    // RRA - Rotate right, then Add to Accumulator with Carry
+    OpcodeEntry::new(0x67, Instruction::RRA, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x77, Instruction::RRA, AddressingMode::ZeroPageX, 2, 6, CycleRule::None),
+    OpcodeEntry::new(0x6F, Instruction::RRA, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    OpcodeEntry::new(0x7F, Instruction::RRA, AddressingMode::AbsoluteX, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x7B, Instruction::RRA, AddressingMode::AbsoluteY, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0x63, Instruction::RRA, AddressingMode::IndirectX, 2, 8, CycleRule::None),
+    OpcodeEntry::new(0x73, Instruction::RRA, AddressingMode::IndirectY, 2, 8, CycleRule::None),
+    // SAX - Store Accumulator AND X
+    OpcodeEntry::new(0x87, Instruction::SAX, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
+    OpcodeEntry::new(0x97, Instruction::SAX, AddressingMode::ZeroPageY, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x8F, Instruction::SAX, AddressingMode::Absolute, 3, 4, CycleRule::None),
+    OpcodeEntry::new(0x83, Instruction::SAX, AddressingMode::IndirectX, 2, 6, CycleRule::None),
+    // LAX - Load Accumulator and X
+    OpcodeEntry::new(0xA7, Instruction::LAX, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
+    OpcodeEntry::new(0xB7, Instruction::LAX, AddressingMode::ZeroPageY, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0xAF, Instruction::LAX, AddressingMode::Absolute, 3, 4, CycleRule::None),
+    OpcodeEntry::new(0xBF, Instruction::LAX, AddressingMode::AbsoluteY, 3, 4, CycleRule::AddOnCross),
+    OpcodeEntry::new(0xA3, Instruction::LAX, AddressingMode::IndirectX, 2, 6, CycleRule::None),
+    OpcodeEntry::new(0xB3, Instruction::LAX, AddressingMode::IndirectY, 2, 5, CycleRule::AddOnCross),
+    // DCP - Decrement, then Compare with Accumulator
+    OpcodeEntry::new(0xC7, Instruction::DCP, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xD7, Instruction::DCP, AddressingMode::ZeroPageX, 2, 6, CycleRule::None),
+    OpcodeEntry::new(0xCF, Instruction::DCP, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    OpcodeEntry::new(0xDF, Instruction::DCP, AddressingMode::AbsoluteX, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0xDB, Instruction::DCP, AddressingMode::AbsoluteY, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0xC3, Instruction::DCP, AddressingMode::IndirectX, 2, 8, CycleRule::None),
+    OpcodeEntry::new(0xD3, Instruction::DCP, AddressingMode::IndirectY, 2, 8, CycleRule::None),
+    // ISC - Increment, then Subtract from Accumulator with Borrow
+    OpcodeEntry::new(0xE7, Instruction::ISC, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xF7, Instruction::ISC, AddressingMode::ZeroPageX, 2, 6, CycleRule::None),
+    OpcodeEntry::new(0xEF, Instruction::ISC, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    OpcodeEntry::new(0xFF, Instruction::ISC, AddressingMode::AbsoluteX, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0xFB, Instruction::ISC, AddressingMode::AbsoluteY, 3, 7, CycleRule::None),
+    OpcodeEntry::new(0xE3, Instruction::ISC, AddressingMode::IndirectX, 2, 8, CycleRule::None),
+    OpcodeEntry::new(0xF3, Instruction::ISC, AddressingMode::IndirectY, 2, 8, CycleRule::None),
+    // ANC - AND with Accumulator, then copy N to Carry
+    OpcodeEntry::new(0x0B, Instruction::ANC, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x2B, Instruction::ANC, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    // ALR - AND with Accumulator, then Shift right
+    OpcodeEntry::new(0x4B, Instruction::ALR, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    // ARR - AND with Accumulator, then Rotate right
+    OpcodeEntry::new(0x6B, Instruction::ARR, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    // AXS - AND X with Accumulator, then Subtract into X
+    OpcodeEntry::new(0xCB, Instruction::AXS, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    // SBC - Subtract Memory from Accumulator with Borrow (duplicate of 0xE9)
+    OpcodeEntry::new(0xEB, Instruction::SBC, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    // LAS - AND with Stack Pointer into Accumulator, X and Stack Pointer
+    OpcodeEntry::new(0xBB, Instruction::LAS, AddressingMode::AbsoluteY, 3, 4, CycleRule::AddOnCross),
+    // XAA - AND X and Accumulator OR magic constant with Memory (unstable)
+    OpcodeEntry::new(0x8B, Instruction::XAA, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    // LXA - AND Accumulator OR magic constant with Memory into Accumulator and X (unstable)
+    OpcodeEntry::new(0xAB, Instruction::LXA, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    // SHA - Store Accumulator AND X AND high address byte + 1 (unstable)
+    OpcodeEntry::new(0x9F, Instruction::SHA, AddressingMode::AbsoluteY, 3, 5, CycleRule::None),
+    OpcodeEntry::new(0x93, Instruction::SHA, AddressingMode::IndirectY, 2, 6, CycleRule::None),
+    // SHX - Store X AND high address byte + 1 (unstable)
+    OpcodeEntry::new(0x9E, Instruction::SHX, AddressingMode::AbsoluteY, 3, 5, CycleRule::None),
+    // SHY - Store Y AND high address byte + 1 (unstable)
+    OpcodeEntry::new(0x9C, Instruction::SHY, AddressingMode::AbsoluteX, 3, 5, CycleRule::None),
+    // TAS - Transfer Accumulator AND X to Stack Pointer, then store it AND high address byte + 1 (unstable)
+    OpcodeEntry::new(0x9B, Instruction::TAS, AddressingMode::AbsoluteY, 3, 5, CycleRule::None),
+    // NOP - No Operation, reading operands like the official instructions do
+    OpcodeEntry::new(0x1A, Instruction::NOP, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x3A, Instruction::NOP, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x5A, Instruction::NOP, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x7A, Instruction::NOP, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0xDA, Instruction::NOP, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0xFA, Instruction::NOP, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x80, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x82, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x89, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0xC2, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0xE2, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x04, Instruction::NOP, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
+    OpcodeEntry::new(0x44, Instruction::NOP, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
+    OpcodeEntry::new(0x64, Instruction::NOP, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
+    OpcodeEntry::new(0x14, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x34, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x54, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x74, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0xD4, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0xF4, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x0C, Instruction::NOP, AddressingMode::Absolute, 3, 4, CycleRule::None),
+    OpcodeEntry::new(0x1C, Instruction::NOP, AddressingMode::AbsoluteX, 3, 4, CycleRule::AddOnCross),
+    OpcodeEntry::new(0x3C, Instruction::NOP, AddressingMode::AbsoluteX, 3, 4, CycleRule::AddOnCross),
+    OpcodeEntry::new(0x5C, Instruction::NOP, AddressingMode::AbsoluteX, 3, 4, CycleRule::AddOnCross),
+    OpcodeEntry::new(0x7C, Instruction::NOP, AddressingMode::AbsoluteX, 3, 4, CycleRule::AddOnCross),
+    OpcodeEntry::new(0xDC, Instruction::NOP, AddressingMode::AbsoluteX, 3, 4, CycleRule::AddOnCross),
+    OpcodeEntry::new(0xFC, Instruction::NOP, AddressingMode::AbsoluteX, 3, 4, CycleRule::AddOnCross),
+    // JAM - Halt the processor until reset
+    OpcodeEntry::new(0x02, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x12, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x22, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x32, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x42, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x52, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x62, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x72, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x92, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0xB2, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0xD2, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0xF2, Instruction::JAM, AddressingMode::Implied, 1, 2, CycleRule::None),
+];
+
+/// Opcodes of the 65C02 that are new or behave differently from [`OPCODE_LIST`]
+#[rustfmt::skip]
+const CMOS_OPCODE_LIST: &[OpcodeEntry] = &[
+    // JMP - Jump to new Location, without the page wrap bug
+    OpcodeEntry::new(0x6C, Instruction::JMP, AddressingMode::Indirect, 3, 6, CycleRule::None),
+    OpcodeEntry::new(0x7C, Instruction::JMP, AddressingMode::AbsoluteIndirectX, 3, 6, CycleRule::None),
+    // ASL, ROL, LSR, ROR - Only take the extra cycle when crossing a page
+    OpcodeEntry::new(0x1E, Instruction::ASL, AddressingMode::AbsoluteX, 3, 6, CycleRule::AddOnCross),
+    OpcodeEntry::new(0x3E, Instruction::ROL, AddressingMode::AbsoluteX, 3, 6, CycleRule::AddOnCross),
+    OpcodeEntry::new(0x5E, Instruction::LSR, AddressingMode::AbsoluteX, 3, 6, CycleRule::AddOnCross),
+    OpcodeEntry::new(0x7E, Instruction::ROR, AddressingMode::AbsoluteX, 3, 6, CycleRule::AddOnCross),
+    // (zp) addressing for the ALU instructions
+    OpcodeEntry::new(0x12, Instruction::ORA, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x32, Instruction::AND, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x52, Instruction::EOR, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x72, Instruction::ADC, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x92, Instruction::STA, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xB2, Instruction::LDA, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xD2, Instruction::CMP, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xF2, Instruction::SBC, AddressingMode::ZeroPageIndirect, 2, 5, CycleRule::None),
+    // BIT - Test bits in memory with accumulator
+    OpcodeEntry::new(0x89, Instruction::BIT, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x34, Instruction::BIT, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x3C, Instruction::BIT, AddressingMode::AbsoluteX, 3, 4, CycleRule::AddOnCross),
+    // INC, DEC - Increment or Decrement Accumulator by One
+    OpcodeEntry::new(0x1A, Instruction::INC, AddressingMode::Implied, 1, 2, CycleRule::None),
+    OpcodeEntry::new(0x3A, Instruction::DEC, AddressingMode::Implied, 1, 2, CycleRule::None),
+    // BRA - Branch Always
+    OpcodeEntry::new(0x80, Instruction::BRA, AddressingMode::Relative, 2, 2, CycleRule::AddOneTwo),
+    // PHX, PHY, PLX, PLY - Push and Pull Index Registers
+    OpcodeEntry::new(0xDA, Instruction::PHX, AddressingMode::Implied, 1, 3, CycleRule::None),
+    OpcodeEntry::new(0x5A, Instruction::PHY, AddressingMode::Implied, 1, 3, CycleRule::None),
+    OpcodeEntry::new(0xFA, Instruction::PLX, AddressingMode::Implied, 1, 4, CycleRule::None),
+    OpcodeEntry::new(0x7A, Instruction::PLY, AddressingMode::Implied, 1, 4, CycleRule::None),
+    // STZ - Store Zero in Memory
+    OpcodeEntry::new(0x64, Instruction::STZ, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
+    OpcodeEntry::new(0x74, Instruction::STZ, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x9C, Instruction::STZ, AddressingMode::Absolute, 3, 4, CycleRule::None),
+    OpcodeEntry::new(0x9E, Instruction::STZ, AddressingMode::AbsoluteX, 3, 5, CycleRule::None),
+    // TRB - Test and Reset Memory Bits with Accumulator
+    OpcodeEntry::new(0x14, Instruction::TRB, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x1C, Instruction::TRB, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    // TSB - Test and Set Memory Bits with Accumulator
+    OpcodeEntry::new(0x04, Instruction::TSB, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x0C, Instruction::TSB, AddressingMode::Absolute, 3, 6, CycleRule::None),
+    // WAI - Wait for Interrupt
+    OpcodeEntry::new(0xCB, Instruction::WAI, AddressingMode::Implied, 1, 3, CycleRule::None),
+    // STP - Stop the Processor
+    OpcodeEntry::new(0xDB, Instruction::STP, AddressingMode::Implied, 1, 3, CycleRule::None),
+    // RMB0-7 - Reset Memory Bit (Rockwell)
+    OpcodeEntry::new(0x07, Instruction::RMB0, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x17, Instruction::RMB1, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x27, Instruction::RMB2, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x37, Instruction::RMB3, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x47, Instruction::RMB4, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x57, Instruction::RMB5, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x67, Instruction::RMB6, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x77, Instruction::RMB7, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    // SMB0-7 - Set Memory Bit (Rockwell)
+    OpcodeEntry::new(0x87, Instruction::SMB0, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0x97, Instruction::SMB1, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xA7, Instruction::SMB2, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xB7, Instruction::SMB3, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xC7, Instruction::SMB4, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xD7, Instruction::SMB5, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xE7, Instruction::SMB6, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    OpcodeEntry::new(0xF7, Instruction::SMB7, AddressingMode::ZeroPage, 2, 5, CycleRule::None),
+    // BBR0-7 - Branch on Memory Bit Reset (Rockwell)
+    OpcodeEntry::new(0x0F, Instruction::BBR0, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x1F, Instruction::BBR1, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x2F, Instruction::BBR2, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x3F, Instruction::BBR3, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x4F, Instruction::BBR4, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x5F, Instruction::BBR5, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x6F, Instruction::BBR6, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x7F, Instruction::BBR7, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    // BBS0-7 - Branch on Memory Bit Set (Rockwell)
+    OpcodeEntry::new(0x8F, Instruction::BBS0, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0x9F, Instruction::BBS1, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0xAF, Instruction::BBS2, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0xBF, Instruction::BBS3, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0xCF, Instruction::BBS4, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0xDF, Instruction::BBS5, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0xEF, Instruction::BBS6, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+    OpcodeEntry::new(0xFF, Instruction::BBS7, AddressingMode::ZeroPageRelative, 3, 5, CycleRule::AddOneTwo),
+];
+
+/// Opcodes left undefined on the 65C02, which do nothing but read their operands
+#[rustfmt::skip]
+const CMOS_UNDOCUMENTED_OPCODE_LIST: &[OpcodeEntry] = &[
+    // NOP - All the remaining opcodes do nothing, reading their operands
+    OpcodeEntry::new(0x02, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x22, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x42, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x62, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x82, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0xC2, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0xE2, Instruction::NOP, AddressingMode::Immediate, 2, 2, CycleRule::None),
+    OpcodeEntry::new(0x44, Instruction::NOP, AddressingMode::ZeroPage, 2, 3, CycleRule::None),
+    OpcodeEntry::new(0x54, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0xD4, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0xF4, Instruction::NOP, AddressingMode::ZeroPageX, 2, 4, CycleRule::None),
+    OpcodeEntry::new(0x5C, Instruction::NOP, AddressingMode::Absolute, 3, 8, CycleRule::None),
+    OpcodeEntry::new(0xDC, Instruction::NOP, AddressingMode::Absolute, 3, 4, CycleRule::None),
+    OpcodeEntry::new(0xFC, Instruction::NOP, AddressingMode::Absolute, 3, 4, CycleRule::None),
+    OpcodeEntry::new(0x03, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x13, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x23, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x33, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x43, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x53, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x63, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x73, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x83, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x93, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xA3, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xB3, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xC3, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xD3, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xE3, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xF3, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x0B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x1B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x2B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x3B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x4B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x5B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x6B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x7B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x8B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0x9B, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xAB, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xBB, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xEB, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+    OpcodeEntry::new(0xFB, Instruction::NOP, AddressingMode::Implied, 1, 1, CycleRule::None),
+];
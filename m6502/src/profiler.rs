@@ -0,0 +1,352 @@
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use super::{bus::Bus, disassembler::Labels, instructions::Instruction, CPU};
+
+/// Executions and cycles of the instruction at one address
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AddressProfile {
+    pub executions: u64,
+    pub cycles: u64,
+}
+
+/// Calls and cycles of a subroutine, or of an interrupt handler
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubroutineProfile {
+    pub calls: u64,
+    /// Cycles from the call to the return, including the subroutines it calls. Counted on
+    /// return, and only once for recursive calls.
+    pub inclusive_cycles: u64,
+    /// Cycles spent in the subroutine itself
+    pub exclusive_cycles: u64,
+}
+
+/// How the rows of a report are ordered. Each orders from the largest down, except
+/// [`Address`](SortBy::Address).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SortBy {
+    Address,
+    /// Executions or calls
+    Count,
+    /// Cycles spent at the address or in the subroutine itself
+    #[default]
+    ExclusiveCycles,
+    /// Cycles including called subroutines. Same as exclusive cycles for addresses.
+    InclusiveCycles,
+}
+
+/// What is running at the moment
+#[derive(Copy, Clone, Debug)]
+enum Item {
+    Instruction {
+        pc: u16,
+        instruction: Option<Instruction>,
+        start_cycles: u64,
+    },
+    /// An interrupt sequence, jumping to `handler`
+    Interrupt { handler: u16, start_cycles: u64 },
+}
+
+impl Item {
+    fn start_cycles(&self) -> u64 {
+        match *self {
+            Item::Instruction { start_cycles, .. } | Item::Interrupt { start_cycles, .. } => {
+                start_cycles
+            }
+        }
+    }
+}
+
+/// A subroutine or interrupt handler in progress
+#[derive(Copy, Clone, Debug)]
+struct Frame {
+    entry: u16,
+    start_cycles: u64,
+    /// The stack pointer after the call. The frame is over once the stack is back above it,
+    /// counting with wrapping arithmetic so that a stack that wraps around is deeper.
+    stack_pointer: u8,
+}
+
+/// Counts where the cycles go, per address and per subroutine.
+///
+/// Attach it to [`CPU::profiler`]. Subroutines are entered with JSR and left when the stack
+/// pointer goes back above where it was after the call, which also catches RTS used as a
+/// jump. TXS leaves all of them, since there's no telling which are still on the new stack.
+/// Interrupt handlers count as subroutines, their entry sequence included.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    addresses: BTreeMap<u16, AddressProfile>,
+    subroutines: BTreeMap<u16, SubroutineProfile>,
+    stack: Vec<Frame>,
+    current: Option<Item>,
+    total_cycles: u64,
+    instructions: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(&self, addr: u16) -> Option<&AddressProfile> {
+        self.addresses.get(&addr)
+    }
+
+    /// The subroutine entered at `entry`
+    pub fn subroutine(&self, entry: u16) -> Option<&SubroutineProfile> {
+        self.subroutines.get(&entry)
+    }
+
+    /// Cycles counted so far, up to the start of the instruction in progress
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// The addresses executed, sorted
+    pub fn addresses(&self, sort: SortBy) -> Vec<(u16, AddressProfile)> {
+        let mut rows: Vec<_> = self.addresses.iter().map(|(&a, &p)| (a, p)).collect();
+        match sort {
+            SortBy::Address => {}
+            SortBy::Count => rows.sort_by_key(|(_, p)| core::cmp::Reverse(p.executions)),
+            SortBy::ExclusiveCycles | SortBy::InclusiveCycles => {
+                rows.sort_by_key(|(_, p)| core::cmp::Reverse(p.cycles))
+            }
+        }
+        rows
+    }
+
+    /// The subroutines called, sorted
+    pub fn subroutines(&self, sort: SortBy) -> Vec<(u16, SubroutineProfile)> {
+        let mut rows: Vec<_> = self.subroutines.iter().map(|(&a, &p)| (a, p)).collect();
+        match sort {
+            SortBy::Address => {}
+            SortBy::Count => rows.sort_by_key(|(_, p)| core::cmp::Reverse(p.calls)),
+            SortBy::ExclusiveCycles => {
+                rows.sort_by_key(|(_, p)| core::cmp::Reverse(p.exclusive_cycles))
+            }
+            SortBy::InclusiveCycles => {
+                rows.sort_by_key(|(_, p)| core::cmp::Reverse(p.inclusive_cycles))
+            }
+        }
+        rows
+    }
+
+    /// Forgets everything counted so far.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Lists the subroutines, then the addresses, at most `limit` of each.
+    pub fn report(&self, sort: SortBy, limit: usize, labels: &impl Labels) -> String {
+        let percent = |cycles: u64| cycles as f64 * 100.0 / self.total_cycles.max(1) as f64;
+        let mut out = format!(
+            "{} cycles, {} instructions\n\n",
+            self.total_cycles, self.instructions
+        );
+        let _ = writeln!(
+            out,
+            "{:<16} {:>10} {:>12} {:>7} {:>12} {:>7}",
+            "Subroutine", "Calls", "Inclusive", "%", "Exclusive", "%"
+        );
+        for (entry, profile) in self.subroutines(sort).into_iter().take(limit) {
+            let _ = writeln!(
+                out,
+                "{:<16} {:>10} {:>12} {:>6.2}% {:>12} {:>6.2}%",
+                name(entry, labels),
+                profile.calls,
+                profile.inclusive_cycles,
+                percent(profile.inclusive_cycles),
+                profile.exclusive_cycles,
+                percent(profile.exclusive_cycles)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "\n{:<16} {:>10} {:>12} {:>7}",
+            "Address", "Executions", "Cycles", "%"
+        );
+        for (addr, profile) in self.addresses(sort).into_iter().take(limit) {
+            let _ = writeln!(
+                out,
+                "{:<16} {:>10} {:>12} {:>6.2}%",
+                name(addr, labels),
+                profile.executions,
+                profile.cycles,
+                percent(profile.cycles)
+            );
+        }
+        out
+    }
+
+    /// Exports everything as JSON, rows sorted like in [`report`](Profiler::report).
+    pub fn to_json(&self, sort: SortBy, labels: &impl Labels) -> String {
+        let mut out = format!(
+            "{{\"total_cycles\":{},\"instructions\":{},\"subroutines\":[",
+            self.total_cycles, self.instructions
+        );
+        for (i, (entry, profile)) in self.subroutines(sort).into_iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            let _ = write!(
+                out,
+                "{separator}{{\"address\":{entry},{}\"calls\":{},\"inclusive_cycles\":{},\"exclusive_cycles\":{}}}",
+                json_label(entry, labels),
+                profile.calls,
+                profile.inclusive_cycles,
+                profile.exclusive_cycles
+            );
+        }
+        out.push_str("],\"addresses\":[");
+        for (i, (addr, profile)) in self.addresses(sort).into_iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            let _ = write!(
+                out,
+                "{separator}{{\"address\":{addr},{}\"executions\":{},\"cycles\":{}}}",
+                json_label(addr, labels),
+                profile.executions,
+                profile.cycles
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+
+    /// Ends the item in progress and starts the next one.
+    fn begin(&mut self, next: Item, stack_pointer: u8) {
+        let cycles = next.start_cycles();
+        if let Some(item) = self.current.take() {
+            let elapsed = cycles.saturating_sub(item.start_cycles());
+            self.total_cycles += elapsed;
+            match item {
+                Item::Instruction { pc, .. } => {
+                    let profile = self.addresses.entry(pc).or_default();
+                    profile.executions += 1;
+                    profile.cycles += elapsed;
+                    self.instructions += 1;
+                }
+                // The handler's time starts with its entry sequence.
+                Item::Interrupt {
+                    handler,
+                    start_cycles,
+                } => self.call(handler, start_cycles, stack_pointer),
+            }
+            if let Some(frame) = self.stack.last() {
+                self.subroutines
+                    .entry(frame.entry)
+                    .or_default()
+                    .exclusive_cycles += elapsed;
+            }
+            let txs = matches!(
+                item,
+                Item::Instruction {
+                    instruction: Some(Instruction::TXS),
+                    ..
+                }
+            );
+            self.unwind(cycles, stack_pointer, txs);
+            if let (
+                Item::Instruction {
+                    instruction: Some(Instruction::JSR),
+                    ..
+                },
+                Item::Instruction { pc, .. },
+            ) = (item, next)
+            {
+                self.call(pc, cycles, stack_pointer);
+            }
+        }
+        self.current = Some(next);
+    }
+
+    fn call(&mut self, entry: u16, start_cycles: u64, stack_pointer: u8) {
+        self.subroutines.entry(entry).or_default().calls += 1;
+        self.stack.push(Frame {
+            entry,
+            start_cycles,
+            stack_pointer,
+        });
+    }
+
+    /// Leaves the subroutines whose frames the stack pointer has gone back above, or all of
+    /// them.
+    fn unwind(&mut self, cycles: u64, stack_pointer: u8, all: bool) {
+        while let Some(frame) = self.stack.last().copied() {
+            // Bytes pulled since the call, negative while the subroutine has more pushed
+            let pulled = stack_pointer.wrapping_sub(frame.stack_pointer) as i8;
+            if !all && pulled <= 0 {
+                break;
+            }
+            self.stack.pop();
+            if !self.stack.iter().any(|outer| outer.entry == frame.entry) {
+                self.subroutines
+                    .entry(frame.entry)
+                    .or_default()
+                    .inclusive_cycles += cycles.saturating_sub(frame.start_cycles);
+            }
+        }
+    }
+}
+
+fn name(addr: u16, labels: &impl Labels) -> String {
+    match labels.label(addr) {
+        Some(label) => String::from(label),
+        None => format!("${addr:04X}"),
+    }
+}
+
+fn json_label(addr: u16, labels: &impl Labels) -> String {
+    let Some(label) = labels.label(addr) else {
+        return String::new();
+    };
+    let mut out = String::from("\"label\":\"");
+    for c in label.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push_str("\",");
+    out
+}
+
+impl<T: Bus> CPU<T> {
+    /// Tells the profiler an instruction is about to run at PC.
+    pub(crate) fn profile_instruction(&mut self) {
+        if self.profiler.is_none() {
+            return;
+        }
+        let item = Item::Instruction {
+            pc: self.pc,
+            instruction: self
+                .decode(self.peek(self.pc))
+                .map(|entry| entry.instruction),
+            start_cycles: self.total_cycles,
+        };
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin(item, self.stack_pointer);
+        }
+    }
+
+    /// Tells the profiler the interrupt sequence through `vector` is about to run.
+    pub(crate) fn profile_interrupt(&mut self, vector: u16) {
+        if self.profiler.is_none() {
+            return;
+        }
+        let item = Item::Interrupt {
+            handler: self.bus.peek_word(vector).unwrap_or_default(),
+            start_cycles: self.total_cycles,
+        };
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin(item, self.stack_pointer);
+        }
+    }
+}
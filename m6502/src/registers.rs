@@ -0,0 +1,42 @@
+use core::fmt;
+
+use super::{bus::Bus, status::Status, CPU};
+
+/// A copy of the registers at one point in time, for frontends to display however they like
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Registers {
+    pub pc: u16,
+    pub ac: u8,
+    pub x: u8,
+    pub y: u8,
+    pub stack_pointer: u8,
+    pub status: Status,
+    /// Cycles taken so far
+    pub total_cycles: u64,
+}
+
+/// Shows the registers on one line, like `PC:0600 A:00 X:00 Y:00 SP:FF P:24 nv-bdIzc`.
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} SP:{:02X} P:{:02X} {}",
+            self.pc, self.ac, self.x, self.y, self.stack_pointer, self.status.byte, self.status
+        )
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    /// Takes a snapshot of the registers.
+    pub fn registers(&self) -> Registers {
+        Registers {
+            pc: self.pc,
+            ac: self.ac,
+            x: self.x,
+            y: self.y,
+            stack_pointer: self.stack_pointer,
+            status: self.status,
+            total_cycles: self.total_cycles,
+        }
+    }
+}
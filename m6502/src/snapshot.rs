@@ -0,0 +1,280 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+use thiserror::Error;
+
+use super::{
+    bus::Bus, cycle_stepping::Replay, registers::Registers, status::Status, variant::Variant, Halt,
+    CPU,
+};
+
+/// Marks the start of a saved snapshot
+const MAGIC: &[u8; 8] = b"M6502SNP";
+/// Format version written by [`Snapshot::to_bytes`]. Snapshots of other versions are rejected.
+pub const SNAPSHOT_VERSION: u16 = 3;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("The bus doesn't support snapshots.")]
+    Unsupported,
+    #[error("Not a snapshot.")]
+    NotASnapshot,
+    #[error("Snapshot version {0} is not supported, expected {SNAPSHOT_VERSION}.")]
+    Version(u16),
+    #[error("Snapshot is truncated.")]
+    Truncated,
+    #[error("Invalid snapshot: {0}.")]
+    Invalid(&'static str),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// The complete state of a CPU and its bus at the end of a cycle, to go back to later or to
+/// save to a file
+#[derive(Clone)]
+pub struct Snapshot {
+    pub registers: Registers,
+    pub data_bus: u8,
+    pub cycles_left: u8,
+    pub nmi_line: bool,
+    pub nmi_pending: bool,
+    pub irq_line: bool,
+    pub variant: Variant,
+    pub undocumented_opcodes: bool,
+    pub halted: Option<Halt>,
+    pub trap_self_jumps: bool,
+    pub waiting: bool,
+    pub cycle_stepped: bool,
+    /// Progress through a cycle-stepped instruction
+    replay: Replay,
+    /// The bus state, as returned by [`Bus::save_state`]
+    pub bus: Vec<u8>,
+}
+
+/// Reads the fields of a saved snapshot in order.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, SnapshotError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::Invalid("flag is neither 0 nor 1")),
+        }
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// Reads what [`save_halt`] wrote.
+    pub(crate) fn halt(&mut self) -> Result<Option<Halt>, SnapshotError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(Halt::Jam {
+                pc: self.u16()?,
+                opcode: self.u8()?,
+            })),
+            2 => Ok(Some(Halt::Trap { pc: self.u16()? })),
+            _ => Err(SnapshotError::Invalid("unknown halt")),
+        }
+    }
+}
+
+/// Writes why the CPU halted, if it did, as a tag byte and the fields of the halt.
+pub(crate) fn save_halt(halted: Option<Halt>, out: &mut Vec<u8>) {
+    match halted {
+        None => out.push(0),
+        Some(Halt::Jam { pc, opcode }) => {
+            out.push(1);
+            out.extend(pc.to_le_bytes());
+            out.push(opcode);
+        }
+        Some(Halt::Trap { pc }) => {
+            out.push(2);
+            out.extend(pc.to_le_bytes());
+        }
+    }
+}
+
+impl Snapshot {
+    /// Encodes the snapshot: the magic bytes, the version, then every field in little endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64 + self.bus.len());
+        out.extend(MAGIC);
+        out.extend(SNAPSHOT_VERSION.to_le_bytes());
+        let registers = &self.registers;
+        out.extend(registers.pc.to_le_bytes());
+        out.extend([
+            registers.ac,
+            registers.x,
+            registers.y,
+            registers.stack_pointer,
+            registers.status.byte,
+        ]);
+        out.extend(registers.total_cycles.to_le_bytes());
+        out.extend([self.data_bus, self.cycles_left]);
+        out.push(match self.variant {
+            Variant::Nmos => 0,
+            Variant::Ricoh2A03 => 1,
+            Variant::Cmos65C02 => 2,
+        });
+        for flag in [
+            self.nmi_line,
+            self.nmi_pending,
+            self.irq_line,
+            self.undocumented_opcodes,
+            self.trap_self_jumps,
+            self.waiting,
+            self.cycle_stepped,
+        ] {
+            out.push(flag as u8);
+        }
+        save_halt(self.halted, &mut out);
+        self.replay.save(&mut out);
+        out.extend((self.bus.len() as u32).to_le_bytes());
+        out.extend(&self.bus);
+        out
+    }
+
+    /// Decodes a snapshot written by [`to_bytes`](Snapshot::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut input = Reader::new(bytes);
+        if input.bytes(MAGIC.len()).ok() != Some(MAGIC) {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = input.u16()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(version));
+        }
+        let registers = Registers {
+            pc: input.u16()?,
+            ac: input.u8()?,
+            x: input.u8()?,
+            y: input.u8()?,
+            stack_pointer: input.u8()?,
+            status: Status { byte: input.u8()? },
+            total_cycles: input.u64()?,
+        };
+        let snapshot = Self {
+            registers,
+            data_bus: input.u8()?,
+            cycles_left: input.u8()?,
+            variant: match input.u8()? {
+                0 => Variant::Nmos,
+                1 => Variant::Ricoh2A03,
+                2 => Variant::Cmos65C02,
+                _ => return Err(SnapshotError::Invalid("unknown variant")),
+            },
+            nmi_line: input.bool()?,
+            nmi_pending: input.bool()?,
+            irq_line: input.bool()?,
+            undocumented_opcodes: input.bool()?,
+            trap_self_jumps: input.bool()?,
+            waiting: input.bool()?,
+            cycle_stepped: input.bool()?,
+            halted: input.halt()?,
+            replay: Replay::load(&mut input)?,
+            bus: {
+                let len = input.u32()? as usize;
+                input.bytes(len)?.to_vec()
+            },
+        };
+        if !input.is_empty() {
+            return Err(SnapshotError::Invalid("trailing data"));
+        }
+        Ok(snapshot)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        Ok(fs::write(path, self.to_bytes())?)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    /// Takes a snapshot of the CPU and the bus. Fails if the bus doesn't support
+    /// [saving its state](Bus::save_state).
+    pub fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        Ok(Snapshot {
+            registers: self.registers(),
+            data_bus: self.data_bus,
+            cycles_left: self.cycles_left,
+            nmi_line: self.nmi_line,
+            nmi_pending: self.nmi_pending,
+            irq_line: self.irq_line,
+            variant: self.variant,
+            undocumented_opcodes: self.undocumented_opcodes,
+            halted: self.halted,
+            trap_self_jumps: self.trap_self_jumps,
+            waiting: self.waiting,
+            cycle_stepped: self.cycle_stepped,
+            replay: self.replay.clone(),
+            bus: self.bus.save_state().ok_or(SnapshotError::Unsupported)?,
+        })
+    }
+
+    /// Goes back to a snapshot. If the bus rejects its state, the registers are left untouched.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        self.bus.load_state(&snapshot.bus)?;
+        let registers = &snapshot.registers;
+        self.pc = registers.pc;
+        self.ac = registers.ac;
+        self.x = registers.x;
+        self.y = registers.y;
+        self.stack_pointer = registers.stack_pointer;
+        self.status = registers.status;
+        self.total_cycles = registers.total_cycles;
+        self.data_bus = snapshot.data_bus;
+        self.cycles_left = snapshot.cycles_left;
+        self.nmi_line = snapshot.nmi_line;
+        self.nmi_pending = snapshot.nmi_pending;
+        self.irq_line = snapshot.irq_line;
+        self.variant = snapshot.variant;
+        self.undocumented_opcodes = snapshot.undocumented_opcodes;
+        self.halted = snapshot.halted;
+        self.trap_self_jumps = snapshot.trap_self_jumps;
+        self.waiting = snapshot.waiting;
+        self.cycle_stepped = snapshot.cycle_stepped;
+        self.replay = snapshot.replay.clone();
+        Ok(())
+    }
+}
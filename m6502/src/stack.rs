@@ -1,25 +1,41 @@
 use super::{bus::Bus, CPU};
 
+/// Address of the bottom of page 1, where the stack lives
+pub const STACK_BASE: u16 = 0x0100;
+
 impl<T: Bus> CPU<T> {
+    /// Writes a byte at the stack pointer, then decrements it.
     pub fn push_byte(&mut self, data: u8) {
-        self.bus.write(0x0100 + self.stack_pointer as usize, data);
-        self.stack_pointer += 1;
+        self.write_bus(STACK_BASE + self.stack_pointer as u16, data);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
     }
+    /// Increments the stack pointer, then reads the byte at it.
     pub fn pull_byte(&mut self) -> u8 {
-        self.stack_pointer -= 1;
-        self.bus.read(0x0100 + self.stack_pointer as usize)
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        self.read_bus(STACK_BASE + self.stack_pointer as u16)
     }
+    /// Pushes the high byte, then the low byte, so that the word ends up little-endian in memory.
     pub fn push_word(&mut self, data: u16) {
-        self.bus
-            .write(0x0100 + self.stack_pointer as usize, (data & 0xFF) as u8);
-        self.bus
-            .write(0x0100 + self.stack_pointer as usize + 1, (data >> 8) as u8);
-        self.stack_pointer += 2;
+        self.push_byte((data >> 8) as u8);
+        self.push_byte((data & 0xFF) as u8);
     }
+    /// Pulls the low byte, then the high byte.
     pub fn pull_word(&mut self) -> u16 {
-        self.stack_pointer -= 2;
-        let low = self.bus.read(0x0100 + self.stack_pointer as usize) as u16;
-        let high = self.bus.read(0x0100 + self.stack_pointer as usize + 1) as u16;
+        let low = self.pull_byte() as u16;
+        let high = self.pull_byte() as u16;
         low | (high << 8)
     }
+    /// Reads the byte at the stack pointer without changing it, as the chip does on the cycle
+    /// it spends incrementing the stack pointer before pulling.
+    pub(crate) fn dummy_stack_read(&mut self) {
+        self.read_bus(STACK_BASE + self.stack_pointer as u16);
+    }
+    /// The bytes currently on the stack with their addresses, from the top (the most recently
+    /// pushed byte, just above the stack pointer) down to 0x01FF.
+    pub fn stack_contents(&self) -> impl Iterator<Item = (u16, u8)> + '_ {
+        (self.stack_pointer as u16 + 1..=0xFF).map(|offset| {
+            let addr = STACK_BASE + offset;
+            (addr, self.peek(addr))
+        })
+    }
 }
@@ -1,5 +1,4 @@
-use egui::text::LayoutJob;
-use egui::{Color32, FontFamily, FontId, TextFormat, Ui};
+use core::fmt;
 
 macro_rules! flag {
     ($f:ident, $set:ident, $bit:expr) => {
@@ -24,7 +23,15 @@ flag!(_ignored_flag, _set_ignored_flag, 5);
 flag!(overflow, set_overflow, 6);
 flag!(negative, set_negative, 7);
 
-#[derive(Clone)]
+/// One flag of the status register, for display
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Flag {
+    /// The letter the flag is known by
+    pub name: char,
+    pub set: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Status {
     pub byte: u8,
 }
@@ -45,37 +52,75 @@ impl Status {
         }
     }
 
-    pub fn render(&mut self, ui: &mut Ui) {
-        let mut layout = LayoutJob::default();
-        fn flag_format(x: bool) -> TextFormat {
-            let color = if x {
-                Color32::RED
+    /// The flags from bit 7 down to bit 0, in `NV-BDIZC` order
+    pub fn flags(&self) -> [Flag; 8] {
+        const NAMES: [char; 8] = ['N', 'V', '-', 'B', 'D', 'I', 'Z', 'C'];
+        core::array::from_fn(|i| Flag {
+            name: NAMES[i],
+            set: self.byte & (0x80 >> i) != 0,
+        })
+    }
+}
+
+/// Shows the flags as `NV-BDIZC`, with clear flags in lowercase.
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for flag in self.flags() {
+            let name = if flag.set {
+                flag.name
             } else {
-                TextFormat::default().color
+                flag.name.to_ascii_lowercase()
             };
-            TextFormat {
-                color,
-                font_id: FontId {
-                    family: FontFamily::Monospace,
-                    ..FontId::default()
-                },
-                ..TextFormat::default()
+            write!(f, "{name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A set of status flags, as their bits in the status register
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Flags(pub u8);
+
+impl Flags {
+    pub const NONE: Flags = Flags(0);
+    pub const CARRY: Flags = Flags(1 << 0);
+    pub const ZERO: Flags = Flags(1 << 1);
+    pub const INTERRUPT_DISABLE: Flags = Flags(1 << 2);
+    pub const DECIMAL: Flags = Flags(1 << 3);
+    pub const OVERFLOW: Flags = Flags(1 << 6);
+    pub const NEGATIVE: Flags = Flags(1 << 7);
+    /// Every flag kept in the register. B and the unused bit only exist on the stack.
+    pub const ALL: Flags = Flags(0b1100_1111);
+
+    pub const fn union(self, other: Flags) -> Flags {
+        Flags(self.0 | other.0)
+    }
+
+    pub const fn contains(self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl core::ops::BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, other: Flags) -> Flags {
+        self.union(other)
+    }
+}
+
+/// Shows the letters of the flags in the set, in `NV-BDIZC` order, like `NZC`.
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for flag in (Status { byte: self.0 }).flags() {
+            if flag.set {
+                write!(f, "{}", flag.name)?;
             }
         }
-        layout.append(
-            &format!("Status flag: {:#04X} (", self.byte),
-            0.0,
-            Default::default(),
-        );
-        layout.append("N", 0.0, flag_format(self.negative()));
-        layout.append("V", 0.0, flag_format(self.overflow()));
-        layout.append("-", 0.0, flag_format(self._ignored_flag()));
-        layout.append("B", 0.0, flag_format(self.break_flag()));
-        layout.append("D", 0.0, flag_format(self.decimal()));
-        layout.append("I", 0.0, flag_format(self.interrupt_disabled()));
-        layout.append("Z", 0.0, flag_format(self.zero()));
-        layout.append("C", 0.0, flag_format(self.carry()));
-        layout.append(")", 0.0, Default::default());
-        ui.label(layout);
+        Ok(())
     }
 }
@@ -0,0 +1,420 @@
+use alloc::{collections::VecDeque, format, string::String, vec::Vec};
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use std::{
+    boxed::Box,
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use super::{
+    addressing_modes::AddressingMode, bus::Bus, disassembler::Disassembly,
+    instructions::Instruction, registers::Registers, CPU,
+};
+
+/// Size of an entry in the binary trace format
+pub const TRACE_RECORD_SIZE: usize = 18;
+
+/// How trace entries are written out
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// The text format of Nintendulator logs, such as the nestest reference log. The PPU
+    /// column assumes an NTSC PPU started on the same cycle as the CPU.
+    Nintendulator,
+    /// The layout of Mesen's default trace logger, without the PPU columns
+    Mesen,
+    /// Fixed-size little-endian records of [`TRACE_RECORD_SIZE`] bytes: PC, A, X, Y, SP, P,
+    /// the three bytes at PC and the cycle count as a 64-bit number
+    Binary,
+}
+
+/// When a [`Tracer`] starts or stops recording
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TraceCondition {
+    /// When the instruction at this address is about to run
+    Pc(u16),
+    /// When this many cycles have passed
+    Cycle(u64),
+}
+
+impl TraceCondition {
+    fn matches(self, registers: &Registers) -> bool {
+        match self {
+            TraceCondition::Pc(pc) => registers.pc == pc,
+            TraceCondition::Cycle(cycle) => registers.total_cycles >= cycle,
+        }
+    }
+}
+
+/// An instruction as it was about to be executed
+#[derive(Copy, Clone, Debug)]
+pub struct TraceEntry {
+    /// Registers before the instruction, with the cycle count it started on
+    pub registers: Registers,
+    pub instruction: Disassembly,
+    /// The address the operand refers to after indexing and indirection, for instructions that
+    /// access memory
+    pub effective_address: Option<u16>,
+    /// The value at the effective address before the instruction
+    pub value: u8,
+    /// For indirect modes, the pointer followed: the zero page address for `(zp,X)`, the base
+    /// address for `(zp),Y`, and the jump target for `JMP (abs)` and `JMP (abs,X)`
+    pub pointer: u16,
+}
+
+impl TraceEntry {
+    /// Captures the instruction at PC, reading memory with [`peek`](CPU::peek).
+    pub fn capture<T: Bus>(cpu: &CPU<T>) -> Self {
+        let instruction = cpu.disassemble(cpu.pc);
+        let low = instruction.raw[1];
+        let absolute = instruction.operand();
+        let word = |addr: u16, high: u16| cpu.peek(addr) as u16 | (cpu.peek(high) as u16) << 8;
+        let zero_page_pointer = |addr: u8| word(addr as u16, addr.wrapping_add(1) as u16);
+        let mode = instruction.entry.map(|entry| entry.addressing_mode);
+        let jump = matches!(
+            instruction.entry.map(|entry| entry.instruction),
+            Some(Instruction::JMP | Instruction::JSR)
+        );
+        let (effective_address, pointer) = match mode {
+            Some(AddressingMode::ZeroPage | AddressingMode::ZeroPageRelative) => {
+                (Some(low as u16), 0)
+            }
+            Some(AddressingMode::ZeroPageX) => (Some(low.wrapping_add(cpu.x) as u16), 0),
+            Some(AddressingMode::ZeroPageY) => (Some(low.wrapping_add(cpu.y) as u16), 0),
+            Some(AddressingMode::Absolute) if !jump => (Some(absolute), 0),
+            Some(AddressingMode::AbsoluteX) => (Some(absolute.wrapping_add(cpu.x as u16)), 0),
+            Some(AddressingMode::AbsoluteY) => (Some(absolute.wrapping_add(cpu.y as u16)), 0),
+            Some(AddressingMode::Indirect) => {
+                // The NMOS chips don't carry into the high byte of the pointer.
+                let high = if cpu.variant.is_cmos() {
+                    absolute.wrapping_add(1)
+                } else {
+                    (absolute & 0xFF00) | (absolute.wrapping_add(1) & 0x00FF)
+                };
+                (None, word(absolute, high))
+            }
+            Some(AddressingMode::AbsoluteIndirectX) => {
+                let addr = absolute.wrapping_add(cpu.x as u16);
+                (None, word(addr, addr.wrapping_add(1)))
+            }
+            Some(AddressingMode::IndirectX) => {
+                let pointer = low.wrapping_add(cpu.x);
+                (Some(zero_page_pointer(pointer)), pointer as u16)
+            }
+            Some(AddressingMode::IndirectY) => {
+                let base = zero_page_pointer(low);
+                (Some(base.wrapping_add(cpu.y as u16)), base)
+            }
+            Some(AddressingMode::ZeroPageIndirect) => (Some(zero_page_pointer(low)), 0),
+            _ => (None, 0),
+        };
+        Self {
+            registers: cpu.registers(),
+            instruction,
+            effective_address,
+            value: effective_address.map_or(0, |addr| cpu.peek(addr)),
+            pointer,
+        }
+    }
+
+    /// Formats the entry like a line of a Nintendulator log.
+    pub fn nintendulator(&self) -> String {
+        let registers = &self.registers;
+        let mut bytes = String::new();
+        for (i, byte) in self.instruction.bytes().iter().enumerate() {
+            let separator = if i == 0 { "" } else { " " };
+            let _ = write!(bytes, "{separator}{byte:02X}");
+        }
+        // Undocumented opcodes are marked with a star.
+        let official = match self.instruction.entry {
+            Some(entry) if !entry.official => '*',
+            _ => ' ',
+        };
+        let dots = registers.total_cycles * 3;
+        format!(
+            "{:04X}  {bytes:<8} {official}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+            registers.pc,
+            self.nintendulator_disassembly(),
+            registers.ac,
+            registers.x,
+            registers.y,
+            registers.status.byte,
+            registers.stack_pointer,
+            dots / 341,
+            dots % 341,
+            registers.total_cycles
+        )
+    }
+
+    /// The instruction, showing the memory it uses like Nintendulator does
+    fn nintendulator_disassembly(&self) -> String {
+        let Some(entry) = self.instruction.entry else {
+            return format!("{}", self.instruction);
+        };
+        let mnemonic = match entry.instruction {
+            Instruction::ISC => "ISB".into(),
+            instruction => format!("{instruction:?}"),
+        };
+        let low = self.instruction.raw[1];
+        let absolute = self.instruction.operand();
+        let addr = self.effective_address.unwrap_or_default();
+        let value = self.value;
+        let pointer = self.pointer;
+        let operand = match entry.addressing_mode {
+            AddressingMode::ZeroPage => format!("${low:02X} = {value:02X}"),
+            AddressingMode::ZeroPageX => format!("${low:02X},X @ {addr:02X} = {value:02X}"),
+            AddressingMode::ZeroPageY => format!("${low:02X},Y @ {addr:02X} = {value:02X}"),
+            AddressingMode::Absolute if self.effective_address.is_some() => {
+                format!("${absolute:04X} = {value:02X}")
+            }
+            AddressingMode::AbsoluteX => format!("${absolute:04X},X @ {addr:04X} = {value:02X}"),
+            AddressingMode::AbsoluteY => format!("${absolute:04X},Y @ {addr:04X} = {value:02X}"),
+            AddressingMode::Indirect => format!("(${absolute:04X}) = {pointer:04X}"),
+            AddressingMode::IndirectX => {
+                format!("(${low:02X},X) @ {pointer:02X} = {addr:04X} = {value:02X}")
+            }
+            AddressingMode::IndirectY => {
+                format!("(${low:02X}),Y = {pointer:04X} @ {addr:04X} = {value:02X}")
+            }
+            _ => {
+                // Same as the plain disassembly, with the mnemonic as spelled above
+                let text = format!("{}", self.instruction);
+                let operand = text.split_once(' ').map_or("", |(_, operand)| operand);
+                String::from(operand)
+            }
+        };
+        if operand.is_empty() {
+            mnemonic
+        } else {
+            format!("{mnemonic} {operand}")
+        }
+    }
+
+    /// Formats the entry in the layout of Mesen's trace logger.
+    pub fn mesen(&self) -> String {
+        let registers = &self.registers;
+        let mut disassembly = format!("{}", self.instruction);
+        let indirect_or_indexed = !matches!(
+            self.instruction.entry.map(|entry| entry.addressing_mode),
+            Some(AddressingMode::ZeroPage | AddressingMode::Absolute)
+        );
+        if let Some(addr) = self.effective_address {
+            if indirect_or_indexed {
+                let _ = write!(disassembly, " [${addr:04X}]");
+            }
+            let _ = write!(disassembly, " = ${:02X}", self.value);
+        }
+        format!(
+            "{:04X}  {disassembly:<40} A:{:02X} X:{:02X} Y:{:02X} S:{:02X} P:{} Cycle:{}",
+            registers.pc,
+            registers.ac,
+            registers.x,
+            registers.y,
+            registers.stack_pointer,
+            registers.status,
+            registers.total_cycles
+        )
+    }
+
+    /// Encodes the entry in the [binary format](TraceFormat::Binary).
+    pub fn to_binary(&self) -> [u8; TRACE_RECORD_SIZE] {
+        let registers = &self.registers;
+        let mut record = [0; TRACE_RECORD_SIZE];
+        record[0..2].copy_from_slice(&registers.pc.to_le_bytes());
+        record[2..7].copy_from_slice(&[
+            registers.ac,
+            registers.x,
+            registers.y,
+            registers.stack_pointer,
+            registers.status.byte,
+        ]);
+        record[7..10].copy_from_slice(&self.instruction.raw);
+        record[10..18].copy_from_slice(&registers.total_cycles.to_le_bytes());
+        record
+    }
+
+    /// Appends the entry to `out` in `format`, text formats with a line break.
+    pub fn encode(&self, format: TraceFormat, out: &mut Vec<u8>) {
+        match format {
+            TraceFormat::Nintendulator => out.extend(self.nintendulator().bytes()),
+            TraceFormat::Mesen => out.extend(self.mesen().bytes()),
+            TraceFormat::Binary => return out.extend(self.to_binary()),
+        }
+        out.push(b'\n');
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TraceState {
+    /// Waiting for the start condition
+    Waiting,
+    Recording,
+    /// The stop condition was met.
+    Stopped,
+}
+
+/// Records the instructions a CPU executes, into a ring buffer or a file.
+///
+/// Attach it to [`CPU::tracer`]. Interrupt sequences aren't recorded.
+pub struct Tracer {
+    /// Recording starts on the first instruction that matches. `None` starts right away.
+    pub start: Option<TraceCondition>,
+    /// Recording stops for good before the first instruction that matches.
+    pub stop: Option<TraceCondition>,
+    state: TraceState,
+    /// Maximum number of entries kept in memory
+    capacity: usize,
+    entries: VecDeque<TraceEntry>,
+    #[cfg(feature = "std")]
+    output: Option<(Box<dyn io::Write>, TraceFormat)>,
+    /// The first error writing to the output
+    #[cfg(feature = "std")]
+    error: Option<io::Error>,
+}
+
+/// A clone keeps the entries in memory, but doesn't write to the output of the original.
+impl Clone for Tracer {
+    fn clone(&self) -> Self {
+        Self {
+            start: self.start,
+            stop: self.stop,
+            state: self.state,
+            capacity: self.capacity,
+            entries: self.entries.clone(),
+            #[cfg(feature = "std")]
+            output: None,
+            #[cfg(feature = "std")]
+            error: None,
+        }
+    }
+}
+
+impl Tracer {
+    /// Keeps the last `capacity` instructions in memory.
+    pub fn ring_buffer(capacity: usize) -> Self {
+        Self {
+            start: None,
+            stop: None,
+            state: TraceState::Waiting,
+            capacity,
+            entries: VecDeque::new(),
+            #[cfg(feature = "std")]
+            output: None,
+            #[cfg(feature = "std")]
+            error: None,
+        }
+    }
+
+    /// Writes every instruction to `output`, keeping none in memory.
+    #[cfg(feature = "std")]
+    pub fn writer(output: impl io::Write + 'static, format: TraceFormat) -> Self {
+        Self {
+            output: Some((Box::new(output), format)),
+            ..Self::ring_buffer(0)
+        }
+    }
+
+    /// Writes every instruction to a new file.
+    #[cfg(feature = "std")]
+    pub fn file(path: impl AsRef<Path>, format: TraceFormat) -> io::Result<Self> {
+        Ok(Self::writer(BufWriter::new(File::create(path)?), format))
+    }
+
+    /// Sets the start condition.
+    pub fn starting_at(mut self, condition: TraceCondition) -> Self {
+        self.start = Some(condition);
+        self
+    }
+
+    /// Sets the stop condition.
+    pub fn stopping_at(mut self, condition: TraceCondition) -> Self {
+        self.stop = Some(condition);
+        self
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.state == TraceState::Recording
+    }
+
+    /// The entries kept in memory, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Encodes the entries kept in memory.
+    pub fn export(&self, format: TraceFormat) -> Vec<u8> {
+        let mut out = Vec::new();
+        for entry in &self.entries {
+            entry.encode(format, &mut out);
+        }
+        out
+    }
+
+    /// Returns the first error writing to the output since the last call. Nothing is written
+    /// after an error.
+    #[cfg(feature = "std")]
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Flushes the output.
+    #[cfg(feature = "std")]
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.output {
+            Some((output, _)) => output.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the start and stop conditions against the registers before an instruction, and
+    /// returns whether it should be recorded.
+    fn update(&mut self, registers: &Registers) -> bool {
+        if self.state == TraceState::Waiting
+            && self.start.is_none_or(|start| start.matches(registers))
+        {
+            self.state = TraceState::Recording;
+        }
+        if self.state == TraceState::Recording
+            && self.stop.is_some_and(|stop| stop.matches(registers))
+        {
+            self.state = TraceState::Stopped;
+        }
+        self.state == TraceState::Recording
+    }
+
+    fn record(&mut self, entry: TraceEntry) {
+        #[cfg(feature = "std")]
+        if let Some((output, format)) = &mut self.output {
+            if self.error.is_none() {
+                let mut encoded = Vec::new();
+                entry.encode(*format, &mut encoded);
+                self.error = output.write_all(&encoded).err();
+            }
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+impl<T: Bus> CPU<T> {
+    /// Hands the instruction about to run to the tracer, if there is one.
+    pub(crate) fn trace(&mut self) {
+        let Some(mut tracer) = self.tracer.take() else {
+            return;
+        };
+        if tracer.update(&self.registers()) {
+            tracer.record(TraceEntry::capture(self));
+        }
+        self.tracer = Some(tracer);
+    }
+}
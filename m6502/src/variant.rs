@@ -0,0 +1,30 @@
+/// A member of the 6502 family the CPU behaves like
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    /// The original MOS NMOS 6502
+    #[default]
+    Nmos,
+    /// The Ricoh 2A03/2A07 used in the NES: an NMOS 6502 with decimal mode disconnected.
+    ///
+    /// The D flag can still be set and cleared, but ADC and SBC always work in binary.
+    Ricoh2A03,
+    /// The CMOS 65C02, with the WDC and Rockwell additions: new instructions and the `(zp)`
+    /// addressing mode, the undocumented opcodes turned into NOPs, the JMP indirect page bug
+    /// fixed and valid N and Z flags in decimal mode.
+    Cmos65C02,
+}
+
+impl Variant {
+    /// Whether ADC and SBC honor the decimal flag
+    pub fn has_decimal_mode(self) -> bool {
+        match self {
+            Variant::Nmos | Variant::Cmos65C02 => true,
+            Variant::Ricoh2A03 => false,
+        }
+    }
+
+    /// Whether this is the 65C02
+    pub fn is_cmos(self) -> bool {
+        self == Variant::Cmos65C02
+    }
+}
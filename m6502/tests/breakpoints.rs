@@ -0,0 +1,120 @@
+//! Stopping at execute, read and write breakpoints, and resuming from them.
+
+mod common;
+
+use common::in_both_modes;
+use m6502::{
+    breakpoints::{Break, BreakpointKind},
+    bus::Ram,
+    variant::Variant,
+    CPU,
+};
+
+/// Counts X up and stores it at $10, reading it back into A, forever.
+const PROGRAM: &[u8] = &[
+    0xA2, 0x00, // LDX #$00
+    0xE8, // INX
+    0x86, 0x10, // STX $10
+    0xA5, 0x10, // LDA $10
+    0x4C, 0x02, 0x06, // JMP $0602
+];
+
+fn cpu(cycle_stepped: bool) -> CPU<Ram> {
+    common::cpu(PROGRAM, Variant::Nmos, cycle_stepped)
+}
+
+/// Runs instructions until a breakpoint is hit.
+fn run(cpu: &mut CPU<Ram>) -> Break {
+    for _ in 0..1000 {
+        if let Some(hit) = cpu.execute().unwrap() {
+            return hit;
+        }
+    }
+    panic!("no breakpoint hit");
+}
+
+#[test]
+fn execute_breakpoints_stop_before_the_instruction() {
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(cycle_stepped);
+        let id = cpu
+            .breakpoints
+            .add(BreakpointKind::Execute, 0x0605..=0x0605);
+        let expected = Break {
+            id,
+            kind: BreakpointKind::Execute,
+            address: 0x0605,
+        };
+        assert_eq!(run(&mut cpu), expected);
+        assert_eq!((cpu.pc, cpu.x, cpu.ac), (0x0605, 1, 0));
+        // Resuming runs the instruction, then stops on the next time around.
+        assert_eq!(run(&mut cpu), expected);
+        assert_eq!((cpu.pc, cpu.x, cpu.ac), (0x0605, 2, 1));
+        assert_eq!(cpu.breakpoints.get(id).unwrap().hits, 2);
+    });
+}
+
+#[test]
+fn watchpoints_stop_after_the_access() {
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(cycle_stepped);
+        let write = cpu.breakpoints.add(BreakpointKind::Write, 0x0008..=0x0010);
+        let read = cpu.breakpoints.add(BreakpointKind::Read, 0x0010..=0x0010);
+        let hit = run(&mut cpu);
+        assert_eq!(
+            (hit.id, hit.kind, hit.address),
+            (write, BreakpointKind::Write, 0x0010)
+        );
+        assert_eq!((cpu.pc, cpu.peek(0x0010)), (0x0605, 1));
+        let hit = run(&mut cpu);
+        assert_eq!(
+            (hit.id, hit.kind, hit.address),
+            (read, BreakpointKind::Read, 0x0010)
+        );
+        assert_eq!((cpu.pc, cpu.ac), (0x0607, 1));
+
+        cpu.breakpoints.set_enabled(write, false);
+        assert_eq!(run(&mut cpu).id, read);
+        assert_eq!(cpu.ac, 2);
+        assert_eq!(cpu.breakpoints.get(write).unwrap().hits, 1);
+        assert_eq!(cpu.breakpoints.get(read).unwrap().hits, 2);
+    });
+}
+
+#[test]
+fn instruction_fetches_are_not_reads() {
+    let mut cpu = cpu(false);
+    // INX reads $0603 again after fetching it, which does count.
+    cpu.breakpoints.add(BreakpointKind::Read, 0x0600..=0x0602);
+    for _ in 0..100 {
+        assert_eq!(cpu.execute().unwrap(), None);
+    }
+}
+
+#[test]
+fn ticks_stop_on_the_cycle_of_the_access() {
+    let mut cpu = cpu(true);
+    cpu.breakpoints.add(BreakpointKind::Write, 0x0010..=0x0010);
+    let mut cycles = 0;
+    while cpu.tick().unwrap().is_none() {
+        cycles += 1;
+    }
+    // LDX and INX take 2 cycles each, and STX writes on its third.
+    assert_eq!(cycles, 6);
+    assert_eq!(cpu.peek(0x0010), 1);
+}
+
+#[test]
+fn removed_breakpoints_keep_the_other_ids() {
+    let mut cpu = cpu(false);
+    let first = cpu
+        .breakpoints
+        .add(BreakpointKind::Execute, 0x0602..=0x0602);
+    let second = cpu
+        .breakpoints
+        .add(BreakpointKind::Execute, 0x0607..=0x0607);
+    assert!(cpu.breakpoints.remove(first).is_some());
+    assert!(cpu.breakpoints.get(first).is_none());
+    assert_eq!(run(&mut cpu).id, second);
+    assert_eq!(cpu.breakpoints.iter().count(), 1);
+}
@@ -0,0 +1,149 @@
+//! Logging code and data through a bank-switching cartridge.
+
+use m6502::{
+    bus::{Device, MemoryMap},
+    cdl::{self, CodeDataLogger},
+    CPU,
+};
+
+const BANK_SIZE: usize = 0x2000;
+
+/// Three 8K banks: $8000-$9FFF shows bank 0 or 1, selected by writing anywhere in the
+/// cartridge, and $E000-$FFFF always shows bank 2.
+struct Cartridge {
+    rom: Vec<u8>,
+    bank: usize,
+}
+
+impl Device for Cartridge {
+    fn read(&mut self, offset: u16) -> Option<u8> {
+        self.peek(offset)
+    }
+
+    fn peek(&self, offset: u16) -> Option<u8> {
+        self.rom_offset(offset).map(|offset| self.rom[offset])
+    }
+
+    fn write(&mut self, _offset: u16, data: u8) {
+        self.bank = data as usize & 1;
+    }
+
+    fn rom_offset(&self, offset: u16) -> Option<usize> {
+        match offset {
+            0x0000..=0x1FFF => Some(self.bank * BANK_SIZE + offset as usize),
+            0x6000..=0x7FFF => Some(2 * BANK_SIZE + offset as usize - 0x6000),
+            _ => None,
+        }
+    }
+}
+
+/// Runs in the fixed bank at $E000.
+const PROGRAM: &[u8] = &[
+    0xA9, 0x01, // LDA #$01
+    0x8D, 0x00, 0x80, // STA $8000: bank 1
+    0x20, 0x00, 0x80, // JSR $8000
+    0xA9, 0x00, // LDA #$00
+    0x8D, 0x00, 0x80, // STA $8000: bank 0
+    0xAD, 0x10, 0x80, // LDA $8010
+    0xA9, 0x00, // LDA #$00
+    0x85, 0x10, // STA $10
+    0xA9, 0x80, // LDA #$80
+    0x85, 0x11, // STA $11
+    0xA0, 0x20, // LDY #$20
+    0xB1, 0x10, // LDA ($10),Y
+    0x6C, 0x30, 0xE0, // JMP ($E030)
+];
+
+fn cpu() -> CPU<MemoryMap> {
+    let mut rom = vec![0; 3 * BANK_SIZE];
+    let fixed = 2 * BANK_SIZE;
+    rom[fixed..fixed + PROGRAM.len()].copy_from_slice(PROGRAM);
+    // The pointer at $E030 leads to an endless loop at $E040.
+    rom[fixed + 0x30..fixed + 0x32].copy_from_slice(&[0x40, 0xE0]);
+    rom[fixed + 0x40..fixed + 0x43].copy_from_slice(&[0x4C, 0x40, 0xE0]);
+    // RTS in bank 1
+    rom[BANK_SIZE] = 0x60;
+    let bus = MemoryMap::builder()
+        .ram(0x0000..=0x07FF)
+        .device(0x8000..=0xFFFF, Cartridge { rom, bank: 0 })
+        .build()
+        .unwrap();
+    let mut cpu = CPU::new(bus);
+    cpu.pc = 0xE000;
+    cpu.code_data_logger = Some(CodeDataLogger::new(3 * BANK_SIZE));
+    cpu
+}
+
+#[test]
+fn marks_code_and_data_per_bank() {
+    let mut cpu = cpu();
+    for _ in 0..20 {
+        cpu.execute().unwrap();
+    }
+    let logger = cpu.code_data_logger.as_ref().unwrap();
+    let fixed = 2 * BANK_SIZE;
+    // The fixed bank is mapped at $E000, the third 8K bank of $8000-$FFFF.
+    let in_fixed_bank = 0x0C;
+    assert_eq!(
+        logger.rom_flags(fixed),
+        cdl::CODE | cdl::OPCODE | in_fixed_bank
+    );
+    assert_eq!(
+        logger.rom_flags(fixed + 1),
+        cdl::CODE | cdl::OPERAND | in_fixed_bank
+    );
+    assert_eq!(logger.rom_flags(fixed + 0x30), cdl::DATA | in_fixed_bank);
+    assert_eq!(
+        logger.rom_flags(fixed + 0x40),
+        cdl::CODE | cdl::OPCODE | cdl::INDIRECT_CODE | in_fixed_bank
+    );
+    // The same address in two banks
+    assert_eq!(
+        logger.rom_flags(BANK_SIZE),
+        cdl::CODE | cdl::OPCODE | cdl::SUBROUTINE | cdl::WRITTEN
+    );
+    assert_eq!(logger.rom_flags(0), cdl::WRITTEN);
+    assert_eq!(logger.rom_flags(0x10), cdl::DATA);
+    assert_eq!(logger.rom_flags(0x20), cdl::DATA | cdl::INDIRECT_DATA);
+    assert_eq!(logger.address_flags(0x10), cdl::WRITTEN | cdl::DATA);
+
+    let exported = logger.to_cdl();
+    assert_eq!(exported.len(), 3 * BANK_SIZE);
+    assert_eq!(
+        (
+            exported[0],
+            exported[BANK_SIZE],
+            exported[0x20],
+            exported[fixed]
+        ),
+        (0x00, 0x81, 0x22, 0x0D)
+    );
+    assert_eq!(logger.rom_coverage(), (PROGRAM.len() + 4, 4));
+}
+
+#[test]
+fn merges_earlier_logs() {
+    let mut logger = CodeDataLogger::new(4);
+    assert!(!logger.merge_cdl(&[0x01; 3]));
+    assert!(logger.merge_cdl(&[0x01, 0x02, 0x00, 0x22]));
+    assert_eq!(logger.to_cdl(), [0x01, 0x02, 0x00, 0x22]);
+    assert_eq!(logger.rom_coverage(), (1, 2));
+}
+
+#[test]
+fn cycle_stepping_logs_the_same() {
+    let logs = [false, true].map(|cycle_stepped| {
+        let mut cpu = cpu();
+        cpu.cycle_stepped = cycle_stepped;
+        for _ in 0..20 {
+            cpu.execute().unwrap();
+        }
+        let logger = cpu.code_data_logger.unwrap();
+        // Skipped accesses would show up at addresses made of zeroed pointer bytes, and a
+        // replayed JSR would mark its own opcode as a subroutine.
+        let rom: Vec<u16> = (0..3 * BANK_SIZE).map(|i| logger.rom_flags(i)).collect();
+        let addresses: Vec<u16> = (0..=0xFFFF).map(|a| logger.address_flags(a)).collect();
+        (rom, addresses)
+    });
+    assert_eq!(logs[0], logs[1]);
+}
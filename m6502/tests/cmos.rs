@@ -0,0 +1,278 @@
+//! What the 65C02 does differently: Klaus Dormann's extended opcodes test and Bruce Clark's
+//! decimal mode test assembled for the 65C02, then the cycles and states those can't see.
+//!
+//! See `fixtures/README.md` for where the binaries come from and how they were assembled.
+
+mod common;
+
+use common::{cpu, in_both_modes};
+use m6502::{
+    bus::{Bus, Ram},
+    status::Flags,
+    variant::Variant,
+    Halt, CPU,
+};
+
+/// Gives up on a test program after this many instructions
+const INSTRUCTION_LIMIT: u64 = 100_000_000;
+
+/// Address of the `jmp *` the extended opcodes test ends in when every test passed
+const EXTENDED_SUCCESS_TRAP: u16 = 0x24F1;
+/// Where the extended opcodes test keeps the number of the test in progress
+const EXTENDED_TEST_CASE: u16 = 0x0202;
+
+/// Address of the `DONE` label the decimal test ends on, passed or not
+const DECIMAL_DONE: u16 = 0x024B;
+/// Where the decimal test stores its result: 0 if it passed, 1 if it failed
+const DECIMAL_ERROR: u16 = 0x000B;
+
+/// Runs one instruction and returns the cycles it took
+fn cycles(cpu: &mut CPU<Ram>) -> u64 {
+    let before = cpu.total_cycles;
+    cpu.execute().unwrap();
+    cpu.total_cycles - before
+}
+
+#[test]
+fn extended_opcodes_test() {
+    let mut ram = Ram::new();
+    ram.load(
+        include_bytes!("fixtures/65C02_extended_opcodes_test.bin"),
+        0,
+    );
+    let mut cpu = CPU::with_variant(ram, Variant::Cmos65C02);
+    cpu.pc = 0x0400;
+    // Every test traps in a branch or jump to itself, the last one on success.
+    cpu.trap_self_jumps = true;
+    for _ in 0..INSTRUCTION_LIMIT {
+        if let Err(e) = cpu.execute() {
+            panic!("{e} in test {:#04x}", cpu.peek(EXTENDED_TEST_CASE));
+        }
+        if let Some(halt) = cpu.halted {
+            assert_eq!(
+                halt,
+                Halt::Trap {
+                    pc: EXTENDED_SUCCESS_TRAP
+                },
+                "{halt} in test {:#04x}",
+                cpu.peek(EXTENDED_TEST_CASE)
+            );
+            return;
+        }
+    }
+    panic!(
+        "no trap after {INSTRUCTION_LIMIT} instructions, PC {:#06x} in test {:#04x}",
+        cpu.pc,
+        cpu.peek(EXTENDED_TEST_CASE)
+    );
+}
+
+#[test]
+fn decimal_test() {
+    let mut ram = Ram::new();
+    ram.load(include_bytes!("fixtures/65C02_decimal_test.bin"), 0x0200);
+    let mut cpu = CPU::with_variant(ram, Variant::Cmos65C02);
+    cpu.pc = 0x0200;
+    for _ in 0..INSTRUCTION_LIMIT {
+        if cpu.pc == DECIMAL_DONE {
+            // The operands and carry of the failing case are left in N1, N2 and Y.
+            assert_eq!(
+                cpu.peek(DECIMAL_ERROR),
+                0,
+                "wrong result for {:#04x} and {:#04x} with carry {}",
+                cpu.peek(0x0000),
+                cpu.peek(0x0001),
+                cpu.y
+            );
+            return;
+        }
+        if let Err(e) = cpu.execute() {
+            panic!("{e}");
+        }
+    }
+    panic!("DONE not reached after {INSTRUCTION_LIMIT} instructions");
+}
+
+#[test]
+fn decimal_flags_and_cycles() {
+    let program = &[
+        0xF8, // SED
+        0x18, // CLC
+        0xA9, 0x99, // LDA #$99
+        0x69, 0x01, // ADC #$01
+        0x38, // SEC
+        0xE9, 0x01, // SBC #$01
+    ];
+    let run = |variant| {
+        let mut cpu = cpu(program, variant, false);
+        for _ in 0..3 {
+            cpu.execute().unwrap();
+        }
+        let adc = cycles(&mut cpu);
+        let after_adc = (cpu.ac, cpu.status.negative(), cpu.status.zero());
+        cpu.execute().unwrap();
+        let sbc = cycles(&mut cpu);
+        (adc, after_adc, sbc, cpu.ac)
+    };
+
+    // $99 + $01: the NMOS 6502 takes N from the sum before the high digit is adjusted and Z
+    // from the binary sum, the 65C02 both from the result, and a cycle more to get them.
+    let (adc, after_adc, sbc, difference) = run(Variant::Nmos);
+    assert_eq!((adc, sbc), (2, 2));
+    assert_eq!(after_adc, (0x00, true, false));
+    assert_eq!(difference, 0x99);
+
+    let (adc, after_adc, sbc, difference) = run(Variant::Cmos65C02);
+    assert_eq!((adc, sbc), (3, 3));
+    assert_eq!(after_adc, (0x00, false, true));
+    assert_eq!(difference, 0x99);
+
+    // No extra cycle in binary mode
+    let mut cpu = cpu(&[0x69, 0x01], Variant::Cmos65C02, false);
+    assert_eq!(cycles(&mut cpu), 2);
+}
+
+#[test]
+fn bit_instructions() {
+    let program = &[
+        0xF7, 0x10, // SMB7 $10
+        0x17, 0x10, // RMB1 $10
+        0x0F, 0x10, 0x03, // BBR0 $10, +3
+        0x1F, 0x10, 0x03, // BBR1 $10, +3
+        0xEA, // NOP
+        0xEA, // NOP
+        0xEA, // NOP
+        0xFF, 0x10, 0xFD, // BBS7 $10, -3
+    ];
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(program, Variant::Cmos65C02, cycle_stepped);
+        cpu.bus.write(0x0010, 0x03);
+        assert_eq!(cycles(&mut cpu), 5);
+        assert_eq!(cpu.peek(0x0010), 0x83);
+        assert_eq!(cycles(&mut cpu), 5);
+        assert_eq!(cpu.peek(0x0010), 0x81);
+        // Bit 0 is set: no branch
+        assert_eq!(cycles(&mut cpu), 5);
+        assert_eq!(cpu.pc, 0x0607);
+        // Bit 1 is clear: branch
+        cpu.execute().unwrap();
+        assert_eq!(cpu.pc, 0x060D);
+        // Bit 7 is set: branch back
+        cpu.execute().unwrap();
+        assert_eq!(cpu.pc, 0x060D);
+        // Nothing but memory changed.
+        assert_eq!(cpu.status, Default::default());
+    });
+}
+
+#[test]
+fn indirect_jump_across_a_page() {
+    let program = &[0x6C, 0xFF, 0x10]; // JMP ($10FF)
+    for (variant, target, jmp_cycles) in [
+        // The NMOS 6502 takes the high byte from the start of the same page.
+        (Variant::Nmos, 0x3412, 5),
+        (Variant::Cmos65C02, 0x5612, 6),
+    ] {
+        in_both_modes(|cycle_stepped| {
+            let mut cpu = cpu(program, variant, cycle_stepped);
+            cpu.bus.write(0x10FF, 0x12);
+            cpu.bus.write(0x1000, 0x34);
+            cpu.bus.write(0x1100, 0x56);
+            assert_eq!(cycles(&mut cpu), jmp_cycles);
+            assert_eq!(cpu.pc, target);
+        });
+    }
+}
+
+#[test]
+fn interrupts_clear_decimal() {
+    let program = &[
+        0xF8, // SED
+        0x00, 0xEA, // BRK
+    ];
+    for (variant, decimal) in [(Variant::Nmos, true), (Variant::Cmos65C02, false)] {
+        in_both_modes(|cycle_stepped| {
+            let mut cpu = cpu(program, variant, cycle_stepped);
+            cpu.bus.write(0xFFFE, 0x00);
+            cpu.bus.write(0xFFFF, 0x07);
+            cpu.execute().unwrap();
+            cpu.execute().unwrap();
+            assert_eq!(cpu.pc, 0x0700);
+            assert_eq!(cpu.status.decimal(), decimal);
+            // Pushed as it was
+            assert!(Flags(cpu.peek(0x01FD)).contains(Flags::DECIMAL));
+
+            // The same for IRQ
+            let mut cpu = common::cpu(&[0x58, 0xF8, 0xEA], variant, cycle_stepped);
+            cpu.bus.write(0xFFFE, 0x00);
+            cpu.bus.write(0xFFFF, 0x07);
+            cpu.execute().unwrap(); // CLI
+            cpu.execute().unwrap(); // SED
+            cpu.set_irq(true);
+            cpu.execute().unwrap();
+            assert_eq!(cpu.pc, 0x0700);
+            assert_eq!(cpu.status.decimal(), decimal);
+        });
+    }
+}
+
+#[test]
+fn wai_waits_for_an_interrupt() {
+    let program = &[
+        0x78, // SEI
+        0xCB, // WAI
+        0xE8, // INX
+        0x58, // CLI
+        0xCB, // WAI
+    ];
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(program, Variant::Cmos65C02, cycle_stepped);
+        cpu.bus.write(0xFFFE, 0x00);
+        cpu.bus.write(0xFFFF, 0x07);
+        cpu.execute().unwrap();
+        assert_eq!(cycles(&mut cpu), 3);
+        assert!(cpu.waiting);
+        for _ in 0..10 {
+            assert_eq!(cycles(&mut cpu), 1);
+        }
+        assert_eq!(cpu.pc, 0x0602);
+
+        // Interrupts are disabled, so this only wakes it up.
+        cpu.set_irq(true);
+        cpu.execute().unwrap();
+        assert!(!cpu.waiting);
+        assert_eq!(cpu.x, 1);
+        cpu.set_irq(false);
+
+        cpu.execute().unwrap();
+        cpu.execute().unwrap();
+        assert!(cpu.waiting);
+        cpu.set_nmi(true);
+        cpu.bus.write(0xFFFA, 0x00);
+        cpu.bus.write(0xFFFB, 0x08);
+        cpu.execute().unwrap();
+        assert!(!cpu.waiting);
+        assert_eq!(cpu.pc, 0x0800);
+    });
+}
+
+#[test]
+fn stp_ignores_interrupts() {
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(&[0x58, 0xDB], Variant::Cmos65C02, cycle_stepped);
+        cpu.execute().unwrap();
+        assert_eq!(cycles(&mut cpu), 3);
+        let halt = Some(Halt::Jam {
+            pc: 0x0601,
+            opcode: 0xDB,
+        });
+        assert_eq!(cpu.halted, halt);
+        cpu.set_irq(true);
+        cpu.set_nmi(true);
+        for _ in 0..10 {
+            assert_eq!(cycles(&mut cpu), 1);
+        }
+        assert_eq!(cpu.halted, halt);
+        assert_eq!(cpu.pc, 0x0602);
+    });
+}
@@ -0,0 +1,41 @@
+//! Setting up a CPU with a small program, shared by the integration tests.
+#![allow(dead_code)]
+
+use m6502::{
+    bus::{Bus, Ram},
+    variant::Variant,
+    CPU,
+};
+
+/// Where programs are loaded, and where the CPU starts
+pub const START: u16 = 0x0600;
+
+/// RAM with each `(address, bytes)` chunk loaded
+pub fn ram(chunks: &[(u16, &[u8])]) -> Ram {
+    let mut ram = Ram::new();
+    for &(addr, bytes) in chunks {
+        ram.load(bytes, addr as usize);
+    }
+    ram
+}
+
+/// A CPU on `bus`, starting at [`START`] with an empty stack
+pub fn cpu_on<B: Bus>(bus: B, variant: Variant, cycle_stepped: bool) -> CPU<B> {
+    let mut cpu = CPU::with_variant(bus, variant);
+    cpu.pc = START;
+    cpu.stack_pointer = 0xFF;
+    cpu.cycle_stepped = cycle_stepped;
+    cpu
+}
+
+/// A CPU with `program` at [`START`]
+pub fn cpu(program: &[u8], variant: Variant, cycle_stepped: bool) -> CPU<Ram> {
+    cpu_on(ram(&[(START, program)]), variant, cycle_stepped)
+}
+
+/// Runs `test` a whole instruction at a time, then cycle-stepped.
+pub fn in_both_modes(mut test: impl FnMut(bool)) {
+    for cycle_stepped in [false, true] {
+        test(cycle_stepped);
+    }
+}
@@ -0,0 +1,276 @@
+//! The bus accesses of each cycle, compared with the cycle-by-cycle tables of "64doc" (John West
+//! and Marko Mäkelä's 6502 documentation), written down like the `cycles` arrays of the
+//! SingleStepTests vectors: address, data, then whether it's a read or a write.
+
+mod common;
+
+use common::{cpu_on, in_both_modes, ram, START};
+use m6502::{
+    bus::{Bus, Ram},
+    variant::Variant,
+    CPU,
+};
+
+/// RAM that logs every access, and how many there were when each cycle ended
+struct Recorder {
+    ram: Ram,
+    accesses: Vec<(u16, u8, &'static str)>,
+    at_tick: Vec<usize>,
+}
+
+impl Bus for Recorder {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let data = self.ram.read(addr);
+        self.accesses.push((addr, data.unwrap(), "read"));
+        data
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        self.ram.peek(addr)
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        self.ram.write(addr, data);
+        self.accesses.push((addr, data, "write"));
+    }
+
+    fn tick(&mut self) {
+        self.at_tick.push(self.accesses.len());
+    }
+}
+
+/// Ticks through `expected` with `program` loaded next to `data`, on a CPU `setup` gets ready.
+/// Cycle-stepped, each tick must make the next access; otherwise, only the order is checked.
+fn assert_cycles(
+    variant: Variant,
+    program: &[u8],
+    data: &[(u16, &[u8])],
+    setup: impl Fn(&mut CPU<Recorder>),
+    expected: &[(u16, u8, &str)],
+) {
+    in_both_modes(|cycle_stepped| {
+        let mut ram = ram(data);
+        ram.load(program, START as usize);
+        let recorder = Recorder {
+            ram,
+            accesses: Vec::new(),
+            at_tick: Vec::new(),
+        };
+        let mut cpu = cpu_on(recorder, variant, cycle_stepped);
+        setup(&mut cpu);
+        for _ in expected {
+            cpu.tick().unwrap();
+        }
+        assert_eq!(cpu.bus.accesses, expected, "cycle-stepped: {cycle_stepped}");
+        if cycle_stepped {
+            let one_each: Vec<usize> = (1..=expected.len()).collect();
+            assert_eq!(cpu.bus.at_tick, one_each);
+        }
+    });
+}
+
+#[test]
+fn implied_dummy_read() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0x0A, 0xEA], // ASL A
+        &[],
+        |_| {},
+        &[(0x0600, 0x0A, "read"), (0x0601, 0xEA, "read")],
+    );
+}
+
+#[test]
+fn indexed_read_across_a_page() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0xBD, 0xFF, 0x12], // LDA $12FF,X
+        &[(0x1200, &[0x11]), (0x1300, &[0x42])],
+        |cpu| cpu.x = 0x01,
+        &[
+            (0x0600, 0xBD, "read"),
+            (0x0601, 0xFF, "read"),
+            (0x0602, 0x12, "read"),
+            // Before the high byte is fixed
+            (0x1200, 0x11, "read"),
+            (0x1300, 0x42, "read"),
+        ],
+    );
+}
+
+#[test]
+fn indexed_indirect_read() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0xA1, 0x20], // LDA ($20,X)
+        &[(0x0020, &[0x99, 0, 0, 0, 0x00, 0x13]), (0x1300, &[0x42])],
+        |cpu| cpu.x = 0x04,
+        &[
+            (0x0600, 0xA1, "read"),
+            (0x0601, 0x20, "read"),
+            // The pointer, before X is added
+            (0x0020, 0x99, "read"),
+            (0x0024, 0x00, "read"),
+            (0x0025, 0x13, "read"),
+            (0x1300, 0x42, "read"),
+        ],
+    );
+}
+
+#[test]
+fn indirect_indexed_write() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0x91, 0x20], // STA ($20),Y
+        &[(0x0020, &[0xFF, 0x12]), (0x1200, &[0x11])],
+        |cpu| {
+            cpu.ac = 0x55;
+            cpu.y = 0x01;
+        },
+        &[
+            (0x0600, 0x91, "read"),
+            (0x0601, 0x20, "read"),
+            (0x0020, 0xFF, "read"),
+            (0x0021, 0x12, "read"),
+            // Stores always read before the high byte is fixed, page crossed or not.
+            (0x1200, 0x11, "read"),
+            (0x1300, 0x55, "write"),
+        ],
+    );
+}
+
+#[test]
+fn read_modify_write() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0xE6, 0x10], // INC $10
+        &[(0x0010, &[0x7F])],
+        |_| {},
+        &[
+            (0x0600, 0xE6, "read"),
+            (0x0601, 0x10, "read"),
+            (0x0010, 0x7F, "read"),
+            // The NMOS 6502 writes the value back unmodified first...
+            (0x0010, 0x7F, "write"),
+            (0x0010, 0x80, "write"),
+        ],
+    );
+    assert_cycles(
+        Variant::Cmos65C02,
+        &[0xE6, 0x10], // INC $10
+        &[(0x0010, &[0x7F])],
+        |_| {},
+        &[
+            (0x0600, 0xE6, "read"),
+            (0x0601, 0x10, "read"),
+            (0x0010, 0x7F, "read"),
+            // ...where the 65C02 reads it again.
+            (0x0010, 0x7F, "read"),
+            (0x0010, 0x80, "write"),
+        ],
+    );
+}
+
+#[test]
+fn stack() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0x48, 0x68], // PHA, PLA
+        &[],
+        |cpu| cpu.ac = 0x33,
+        &[
+            (0x0600, 0x48, "read"),
+            (0x0601, 0x68, "read"),
+            (0x01FF, 0x33, "write"),
+            (0x0601, 0x68, "read"),
+            (0x0602, 0x00, "read"),
+            // The stack pointer is incremented on this cycle.
+            (0x01FE, 0x00, "read"),
+            (0x01FF, 0x33, "read"),
+        ],
+    );
+}
+
+#[test]
+fn subroutine_call_and_return() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0x20, 0x00, 0x07],  // JSR $0700
+        &[(0x0700, &[0x60])], // RTS
+        |_| {},
+        &[
+            (0x0600, 0x20, "read"),
+            (0x0601, 0x00, "read"),
+            (0x01FF, 0x00, "read"),
+            // The address of the last byte of the JSR is pushed.
+            (0x01FF, 0x06, "write"),
+            (0x01FE, 0x02, "write"),
+            (0x0602, 0x07, "read"),
+            (0x0700, 0x60, "read"),
+            (0x0701, 0x00, "read"),
+            (0x01FD, 0x00, "read"),
+            (0x01FE, 0x02, "read"),
+            (0x01FF, 0x06, "read"),
+            // PC is incremented past it on the last cycle.
+            (0x0602, 0x07, "read"),
+        ],
+    );
+}
+
+#[test]
+fn branch_across_a_page() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0xF0, 0xFD, 0xEA], // BEQ $05FF
+        &[],
+        |cpu| cpu.status.set_zero(true),
+        &[
+            (0x0600, 0xF0, "read"),
+            (0x0601, 0xFD, "read"),
+            (0x0602, 0xEA, "read"),
+            // Before the high byte is fixed
+            (0x06FF, 0x00, "read"),
+        ],
+    );
+}
+
+#[test]
+fn brk() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0x00, 0xEA], // BRK
+        &[(0xFFFE, &[0x00, 0x07])],
+        |_| {},
+        &[
+            (0x0600, 0x00, "read"),
+            (0x0601, 0xEA, "read"),
+            (0x01FF, 0x06, "write"),
+            (0x01FE, 0x02, "write"),
+            // With B set
+            (0x01FD, 0x30, "write"),
+            (0xFFFE, 0x00, "read"),
+            (0xFFFF, 0x07, "read"),
+        ],
+    );
+}
+
+#[test]
+fn irq() {
+    assert_cycles(
+        Variant::Nmos,
+        &[0xEA], // NOP, not executed
+        &[(0xFFFE, &[0x00, 0x07])],
+        |cpu| cpu.set_irq(true),
+        &[
+            // The opcode fetch is made and ignored, and so is the next read.
+            (0x0600, 0xEA, "read"),
+            (0x0600, 0xEA, "read"),
+            (0x01FF, 0x06, "write"),
+            (0x01FE, 0x00, "write"),
+            // With B clear
+            (0x01FD, 0x20, "write"),
+            (0xFFFE, 0x00, "read"),
+            (0xFFFF, 0x07, "read"),
+        ],
+    );
+}
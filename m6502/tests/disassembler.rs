@@ -0,0 +1,143 @@
+//! Disassembling instructions: the operand syntax of every addressing mode, labels, branch
+//! targets, and what happens at the end of memory.
+
+mod common;
+
+use std::collections::{BTreeMap, HashMap};
+
+use common::{ram, START};
+use m6502::{bus::Ram, disassembler, variant::Variant, CPU};
+
+fn cpu(program: &[u8], variant: Variant) -> CPU<Ram> {
+    CPU::with_variant(ram(&[(START, program)]), variant)
+}
+
+/// The instruction at [`START`], formatted without labels
+fn disassemble(program: &[u8], variant: Variant) -> String {
+    disassembler::disassemble(&ram(&[(START, program)]), START, variant, false).to_string()
+}
+
+#[test]
+fn addressing_modes() {
+    for (program, text) in [
+        (&[0x18][..], "CLC"),
+        (&[0x0A], "ASL A"),
+        (&[0xA9, 0x12], "LDA #$12"),
+        (&[0xA5, 0x12], "LDA $12"),
+        (&[0xB5, 0x12], "LDA $12,X"),
+        (&[0xB6, 0x12], "LDX $12,Y"),
+        (&[0xAD, 0x34, 0x12], "LDA $1234"),
+        (&[0xBD, 0x34, 0x12], "LDA $1234,X"),
+        (&[0xB9, 0x34, 0x12], "LDA $1234,Y"),
+        (&[0x6C, 0x34, 0x12], "JMP ($1234)"),
+        (&[0xA1, 0x12], "LDA ($12,X)"),
+        (&[0xB1, 0x12], "LDA ($12),Y"),
+        // A zero page address in an absolute instruction keeps its four digits.
+        (&[0xAD, 0x12, 0x00], "LDA $0012"),
+    ] {
+        assert_eq!(disassemble(program, Variant::Nmos), text);
+    }
+    for (program, text) in [
+        (&[0x1A][..], "INC A"),
+        (&[0xB2, 0x12], "LDA ($12)"),
+        (&[0x7C, 0x34, 0x12], "JMP ($1234,X)"),
+        (&[0x0F, 0x12, 0x10], "BBR0 $12,$0613"),
+    ] {
+        assert_eq!(disassemble(program, Variant::Cmos65C02), text);
+    }
+}
+
+#[test]
+fn branch_targets() {
+    let branch = |program: &[u8], variant| cpu(program, variant).disassemble(START);
+
+    let forward = branch(&[0xD0, 0x10], Variant::Nmos);
+    assert_eq!(forward.branch_target(), Some(0x0612));
+    assert_eq!(forward.to_string(), "BNE $0612");
+    let to_itself = branch(&[0xD0, 0xFE], Variant::Nmos);
+    assert_eq!(to_itself.branch_target(), Some(0x0600));
+    assert_eq!(to_itself.to_string(), "BNE $0600");
+    // Counted from after the three bytes
+    let back = branch(&[0x8F, 0x12, 0xF0], Variant::Cmos65C02);
+    assert_eq!(back.branch_target(), Some(0x05F3));
+    assert_eq!(back.to_string(), "BBS0 $12,$05F3");
+
+    assert_eq!(
+        branch(&[0x4C, 0x00, 0x06], Variant::Nmos).branch_target(),
+        None
+    );
+}
+
+#[test]
+fn labels() {
+    let labels: BTreeMap<u16, String> = [
+        (0x0012, "ptr".to_string()),
+        (0x1234, "table".to_string()),
+        (0x0600, "loop".to_string()),
+    ]
+    .into();
+    let hash_labels: HashMap<u16, String> = labels.clone().into_iter().collect();
+    for (program, variant, text) in [
+        (&[0xB1, 0x12][..], Variant::Nmos, "LDA (ptr),Y"),
+        (&[0xBD, 0x34, 0x12], Variant::Nmos, "LDA table,X"),
+        (&[0x6C, 0x34, 0x12], Variant::Nmos, "JMP (table)"),
+        (&[0xD0, 0xFE], Variant::Nmos, "BNE loop"),
+        (&[0x0F, 0x12, 0xFD], Variant::Cmos65C02, "BBR0 ptr,loop"),
+        // Not an address
+        (&[0xA9, 0x12], Variant::Nmos, "LDA #$12"),
+        // No label for it
+        (&[0xAD, 0x35, 0x12], Variant::Nmos, "LDA $1235"),
+    ] {
+        let instruction = cpu(program, variant).disassemble(START);
+        assert_eq!(instruction.format_with(&labels), text);
+        assert_eq!(instruction.format_with(&hash_labels), text);
+        let mut written = String::new();
+        instruction.write_with(&mut written, &labels).unwrap();
+        assert_eq!(written, text);
+    }
+}
+
+#[test]
+fn undefined_opcodes() {
+    let mut cpu = cpu(&[0x02, 0xA9, 0x01], Variant::Nmos);
+    let unknown = cpu.disassemble(START);
+    assert_eq!(unknown.to_string(), ".byte $02");
+    assert_eq!(unknown.size(), 1);
+    assert_eq!(unknown.bytes(), [0x02]);
+    // The next byte is decoded on its own.
+    let texts: Vec<String> = cpu
+        .disassemble_range(START, START + 1)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(texts, [".byte $02", "LDA #$01"]);
+
+    cpu.undocumented_opcodes = true;
+    assert_eq!(cpu.disassemble(START).to_string(), "JAM");
+    let jam = disassembler::disassemble(&cpu.bus, START, Variant::Nmos, true);
+    assert_eq!(jam.to_string(), "JAM");
+}
+
+#[test]
+fn end_of_memory() {
+    let cpu = CPU::new(ram(&[
+        (0xFFFD, &[0xEA, 0xEA, 0xAD]), // NOP, NOP, LDA $1234
+        (0x0000, &[0x34, 0x12]),
+    ]));
+    let last = cpu.disassemble(0xFFFF);
+    assert_eq!(last.to_string(), "LDA $1234");
+    assert_eq!(last.bytes(), [0xAD, 0x34, 0x12]);
+    assert_eq!(last.next(), 0x0002);
+    // The range stops at the end of memory instead of wrapping around.
+    let addresses: Vec<u16> = cpu
+        .disassemble_range(0xFFFD, 0xFFFF)
+        .iter()
+        .map(|instruction| instruction.address)
+        .collect();
+    assert_eq!(addresses, [0xFFFD, 0xFFFE, 0xFFFF]);
+
+    let cpu = CPU::new(ram(&[(0xFFFE, &[0xD0, 0x02])])); // BNE $0002
+    let branch = cpu.disassemble(0xFFFE);
+    assert_eq!(branch.next(), 0x0000);
+    assert_eq!(branch.to_string(), "BNE $0002");
+}
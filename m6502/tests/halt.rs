@@ -0,0 +1,111 @@
+//! Programs halting the CPU, and the emulator failing with the state it failed in.
+
+mod common;
+
+use common::{cpu, in_both_modes};
+use m6502::{
+    bus::{Bus, Ram},
+    snapshot::Snapshot,
+    variant::Variant,
+    ErrorKind, Halt, CPU,
+};
+
+#[test]
+fn errors_tell_where_they_happened() {
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(
+            &[
+                0xA9, 0x01, // LDA #$01
+                0x02, 0xEA, // JAM, not enabled
+            ],
+            Variant::Nmos,
+            cycle_stepped,
+        );
+        cpu.execute().unwrap();
+        let e = cpu.execute().unwrap_err();
+        assert!(matches!(e.kind, ErrorKind::NoInstruction(0x02)));
+        assert_eq!(e.pc, 0x0602);
+        assert_eq!(e.instruction.bytes(), [0x02]);
+        assert_eq!(e.registers.ac, 0x01);
+        assert_eq!(e.registers.total_cycles, 2);
+        assert_eq!(
+            e.to_string(),
+            "No instruction implemented for opcode 0x02. At $0602 (.byte $02), \
+             PC:0603 A:01 X:00 Y:00 SP:FF P:20 nv-bdizc, cycle 2"
+        );
+        assert_eq!(cpu.halted, None);
+    });
+}
+
+#[test]
+fn jam_halts_until_reset() {
+    for (variant, opcode) in [(Variant::Nmos, 0x02), (Variant::Cmos65C02, 0xDB)] {
+        in_both_modes(|cycle_stepped| {
+            let mut cpu = cpu(&[0xEA, opcode], variant, cycle_stepped);
+            cpu.undocumented_opcodes = true;
+            cpu.bus.write(0xFFFC, 0x00);
+            cpu.bus.write(0xFFFD, 0x06);
+            cpu.execute().unwrap();
+            cpu.execute().unwrap();
+            let halt = Halt::Jam { pc: 0x0601, opcode };
+            assert_eq!(cpu.halted, Some(halt));
+            // The program is over, but nothing went wrong.
+            for _ in 0..10 {
+                cpu.tick().unwrap();
+            }
+            assert_eq!(cpu.halted, Some(halt));
+
+            cpu.reset();
+            assert_eq!(cpu.halted, None);
+            assert_eq!(cpu.pc, 0x0600);
+        });
+    }
+}
+
+#[test]
+fn traps_jumps_to_self() {
+    let program = &[
+        0xA2, 0x03, // LDX #$03
+        0xCA, // DEX
+        0xD0, 0xFD, // BNE $0602
+        0x4C, 0x05, 0x06, // JMP $0605
+    ];
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(program, Variant::Nmos, cycle_stepped);
+        cpu.trap_self_jumps = true;
+        for _ in 0..20 {
+            cpu.execute().unwrap();
+        }
+        // Looping back to another instruction doesn't count.
+        assert_eq!(cpu.halted, Some(Halt::Trap { pc: 0x0605 }));
+        assert_eq!(cpu.x, 0);
+        assert_eq!(cpu.halted.unwrap().to_string(), "trapped at $0605");
+    });
+
+    let mut cpu = cpu(program, Variant::Nmos, false);
+    for _ in 0..20 {
+        cpu.execute().unwrap();
+    }
+    assert_eq!(cpu.halted, None);
+}
+
+#[test]
+fn snapshots_keep_halts() {
+    let mut cpu = cpu(&[0xDB], Variant::Cmos65C02, false);
+    cpu.trap_self_jumps = true;
+    cpu.execute().unwrap();
+    let bytes = cpu.snapshot().unwrap().to_bytes();
+
+    let mut restored = CPU::new(Ram::new());
+    restored
+        .restore(&Snapshot::from_bytes(&bytes).unwrap())
+        .unwrap();
+    assert_eq!(
+        restored.halted,
+        Some(Halt::Jam {
+            pc: 0x0600,
+            opcode: 0xDB
+        })
+    );
+    assert!(restored.trap_self_jumps);
+}
@@ -0,0 +1,85 @@
+//! Klaus Dormann's functional test and Bruce Clark's decimal mode test, run on the NMOS 6502.
+//!
+//! See `fixtures/README.md` for where the binaries come from and how they were assembled.
+
+use m6502::{bus::Ram, Halt, CPU};
+
+/// Gives up on a test program after this many instructions
+const INSTRUCTION_LIMIT: u64 = 100_000_000;
+
+/// Address of the `jmp *` the functional test ends in when every test passed
+const FUNCTIONAL_SUCCESS_TRAP: u16 = 0x3469;
+/// Where the functional test keeps the number of the test in progress
+const FUNCTIONAL_TEST_CASE: u16 = 0x0200;
+
+/// Address of the `DONE` label the decimal test ends on, passed or not
+const DECIMAL_DONE: u16 = 0x024B;
+/// Where the decimal test stores its result: 0 if it passed, 1 if it failed
+const DECIMAL_ERROR: u16 = 0x000B;
+
+fn load(image: &[u8], at: usize, start: u16) -> CPU<Ram> {
+    let mut ram = Ram::new();
+    ram.load(image, at);
+    let mut cpu = CPU::new(ram);
+    cpu.pc = start;
+    cpu
+}
+
+#[test]
+fn functional_test() {
+    let mut cpu = load(
+        include_bytes!("fixtures/6502_functional_test.bin"),
+        0x0000,
+        0x0400,
+    );
+    // Every test traps in a branch or jump to itself, the last one on success.
+    cpu.trap_self_jumps = true;
+    for _ in 0..INSTRUCTION_LIMIT {
+        if let Err(e) = cpu.execute() {
+            panic!("{e} in test {:#04x}", cpu.peek(FUNCTIONAL_TEST_CASE));
+        }
+        if let Some(halt) = cpu.halted {
+            assert_eq!(
+                halt,
+                Halt::Trap {
+                    pc: FUNCTIONAL_SUCCESS_TRAP
+                },
+                "{halt} in test {:#04x}",
+                cpu.peek(FUNCTIONAL_TEST_CASE)
+            );
+            return;
+        }
+    }
+    panic!(
+        "no trap after {INSTRUCTION_LIMIT} instructions, PC {:#06x} in test {:#04x}",
+        cpu.pc,
+        cpu.peek(FUNCTIONAL_TEST_CASE)
+    );
+}
+
+#[test]
+fn decimal_test() {
+    let mut cpu = load(
+        include_bytes!("fixtures/6502_decimal_test.bin"),
+        0x0200,
+        0x0200,
+    );
+    for _ in 0..INSTRUCTION_LIMIT {
+        if cpu.pc == DECIMAL_DONE {
+            // The operands and carry of the failing case are left in N1, N2 and Y.
+            assert_eq!(
+                cpu.peek(DECIMAL_ERROR),
+                0,
+                "wrong result for {:#04x} and {:#04x} with carry {}",
+                cpu.peek(0x0000),
+                cpu.peek(0x0001),
+                cpu.y
+            );
+            return;
+        }
+        if let Err(e) = cpu.execute() {
+            panic!("{e}");
+        }
+    }
+    panic!("DONE not reached after {INSTRUCTION_LIMIT} instructions");
+}
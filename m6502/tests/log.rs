@@ -0,0 +1,60 @@
+//! Logging executed instructions through the `log` crate.
+#![cfg(feature = "log")]
+
+mod common;
+
+use std::sync::Mutex;
+
+use common::{cpu, in_both_modes};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use m6502::variant::Variant;
+
+/// Keeps the lines logged at debug level
+struct Lines(Mutex<Vec<String>>);
+
+impl Log for Lines {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LINES: Lines = Lines(Mutex::new(Vec::new()));
+
+#[test]
+fn logs_each_instruction_once() {
+    log::set_logger(&LINES).unwrap();
+    log::set_max_level(LevelFilter::Debug);
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(
+            &[
+                0xA9, 0x01, // LDA #$01
+                0x85, 0x10, // STA $10
+                0xE6, 0x10, // INC $10
+                0xEA, // NOP
+            ],
+            Variant::Nmos,
+            cycle_stepped,
+        );
+        LINES.0.lock().unwrap().clear();
+        // 2 + 3 + 5 cycles, replayed up to five times when cycle-stepped
+        for _ in 0..10 {
+            cpu.tick().unwrap();
+        }
+        assert_eq!(
+            *LINES.0.lock().unwrap(),
+            [
+                "PC:0x0600 LDA #$01",
+                "PC:0x0602 STA $10",
+                "PC:0x0604 INC $10"
+            ]
+        );
+    });
+}
@@ -0,0 +1,301 @@
+//! Building memory maps and running code on them.
+
+use std::{cell::Cell, ops::RangeInclusive, rc::Rc};
+
+use m6502::{
+    bus::{Bus, Device, MapError, MemoryMap, Unmapped, UnmappedAccess},
+    snapshot::SnapshotError,
+    ErrorKind, CPU,
+};
+
+/// A device that remembers the last write and counts reads and ticks
+#[derive(Default)]
+struct Latch {
+    value: u8,
+    reads: Rc<Cell<u32>>,
+    ticks: Rc<Cell<u32>>,
+}
+
+impl Device for Latch {
+    fn name(&self) -> &str {
+        "Latch"
+    }
+
+    fn read(&mut self, offset: u16) -> Option<u8> {
+        self.reads.set(self.reads.get() + 1);
+        Some(self.value.wrapping_add(offset as u8))
+    }
+
+    fn write(&mut self, _offset: u16, data: u8) {
+        self.value = data;
+    }
+
+    fn tick(&mut self) {
+        self.ticks.set(self.ticks.get() + 1);
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Some(vec![self.value])
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), SnapshotError> {
+        let &[value] = state else {
+            return Err(SnapshotError::Invalid("a latch is one byte"));
+        };
+        self.value = value;
+        Ok(())
+    }
+}
+
+#[test]
+fn runs_code_from_rom_with_a_device() {
+    let latch = Latch::default();
+    let (reads, ticks) = (latch.reads.clone(), latch.ticks.clone());
+    // LDA #$41; STA $4000; LDA $4003; STA $0801; JMP $8000
+    let mut rom = vec![
+        0xA9, 0x41, 0x8D, 0x00, 0x40, 0xAD, 0x03, 0x40, 0x8D, 0x01, 0x08, 0x4C, 0x00, 0x80,
+    ];
+    rom.resize(0x8000, 0xEA);
+    rom[0x7FFC..].copy_from_slice(&[0x00, 0x80, 0x00, 0x80]);
+    let bus = MemoryMap::builder()
+        .ram(0x0000..=0x07FF)
+        .mirror(0x0800..=0x1FFF, 0x0000..=0x07FF)
+        .device(0x4000..=0x4003, latch)
+        .rom(0x8000..=0xFFFF, rom)
+        .build()
+        .unwrap();
+    let mut cpu = CPU::new(bus);
+    cpu.reset();
+    // The reset sequence takes 7 cycles and the five instructions 17.
+    for _ in 0..24 {
+        cpu.tick().unwrap();
+    }
+    assert_eq!(cpu.pc, 0x8000);
+    assert_eq!(cpu.peek(0x0001), 0x44);
+    assert_eq!(cpu.peek(0x1001), 0x44);
+    assert_eq!(reads.get(), 1);
+    assert_eq!(ticks.get(), 24);
+    // Devices can't be peeked by default, and ROM ignores writes.
+    assert_eq!(cpu.bus.peek(0x4000), None);
+    cpu.bus.write(0x8000, 0x00);
+    assert_eq!(cpu.bus.peek(0x8000), Some(0xA9));
+}
+
+#[test]
+fn rejects_overlapping_regions() {
+    let error = MemoryMap::builder()
+        .ram(0x0000..=0x07FF)
+        .rom(0x8000..=0xFFFF, [0; 0x8000])
+        .ram(0x0400..=0x0FFF)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "$0000-$07FF RAM (2048 bytes) overlaps $0400-$0FFF RAM (3072 bytes)."
+    );
+    let error = MemoryMap::builder()
+        .mirror(0x0800..=0x0FFF, RangeInclusive::new(0x0100, 0x00FF))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        MapError::EmptyRange {
+            start: 0x0100,
+            end: 0x00FF
+        }
+    );
+}
+
+#[test]
+fn rejects_bad_mirrors() {
+    let error = MemoryMap::builder()
+        .ram(0x0000..=0x07FF)
+        .mirror(0x0800..=0x1FFF, 0x0000..=0x0FFF)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        MapError::MirrorsItself("$0800-$1FFF mirror of $0000-$0FFF".into())
+    );
+    let error = MemoryMap::builder()
+        .ram(0x0000..=0x07FF)
+        .mirror(0x0800..=0x0FFF, 0x0000..=0x07FF)
+        .mirror(0x1000..=0x1FFF, 0x0800..=0x0FFF)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "$1000-$1FFF mirror of $0800-$0FFF shows $0800-$0FFF mirror of $0000-$07FF, and \
+         mirrors of mirrors aren't followed."
+    );
+}
+
+#[test]
+fn states_are_checked_before_loading() {
+    let build = |ram_end| {
+        MemoryMap::builder()
+            .ram(0x0000..=ram_end)
+            .device(0x2000..=0x2007, Latch::default())
+            .build()
+            .unwrap()
+    };
+    let mut bus = build(0x07FF);
+    bus.write(0x0001, 0x11);
+    bus.write(0x2000, 0x22);
+    let state = bus.save_state().unwrap();
+    let mut copy = build(0x07FF);
+    copy.load_state(&state).unwrap();
+    assert_eq!(copy.peek(0x0001), Some(0x11));
+    assert_eq!(copy.read(0x2000), Some(0x22));
+
+    // Nothing is loaded from the state of another map, or from a cut one.
+    let mut smaller = build(0x03FF);
+    smaller.write(0x0001, 0x33);
+    assert!(matches!(
+        smaller.load_state(&state),
+        Err(SnapshotError::Invalid(_))
+    ));
+    assert_eq!(smaller.peek(0x0001), Some(0x33));
+    let mut fresh = build(0x07FF);
+    assert!(matches!(
+        fresh.load_state(&state[..state.len() - 1]),
+        Err(SnapshotError::Truncated)
+    ));
+    assert_eq!(fresh.peek(0x0001), Some(0x00));
+    // Nor when a device rejects its own state: the RAM before it is loaded last.
+    let mut bad = state[..0x0806].to_vec();
+    bad.extend(2u32.to_le_bytes());
+    bad.extend([0x22, 0x00]);
+    assert!(matches!(
+        fresh.load_state(&bad),
+        Err(SnapshotError::Invalid("a latch is one byte"))
+    ));
+    assert_eq!(fresh.peek(0x0001), Some(0x00));
+}
+
+#[test]
+fn unmapped_policies() {
+    let build = |unmapped| {
+        MemoryMap::builder()
+            .ram(0x0000..=0x00FF)
+            .unmapped(unmapped)
+            .build()
+            .unwrap()
+    };
+    let mut open_bus = build(Unmapped::OpenBus);
+    assert_eq!(open_bus.read(0x1234), None);
+
+    let mut zero = build(Unmapped::Zero);
+    assert_eq!(zero.read(0x1234), Some(0));
+
+    let mut error = build(Unmapped::Error);
+    assert_eq!(error.read(0x0012), Some(0));
+    assert_eq!(error.take_fault(), None);
+    error.write(0x2000, 1);
+    error.read(0x3000);
+    assert_eq!(
+        error.take_fault(),
+        Some(UnmappedAccess {
+            addr: 0x2000,
+            write: true
+        })
+    );
+    assert_eq!(error.take_fault(), None);
+}
+
+#[test]
+fn unmapped_accesses_fail_the_cpu() {
+    let program = [
+        0xA9, 0x01, // LDA #$01
+        0x8D, 0x00, 0x20, // STA $2000
+    ];
+    for cycle_stepped in [false, true] {
+        let mut rom = program.to_vec();
+        rom.resize(0x1000, 0xEA);
+        let bus = MemoryMap::builder()
+            .ram(0x0000..=0x07FF)
+            .rom(0xF000..=0xFFFF, rom)
+            .unmapped(Unmapped::Error)
+            .build()
+            .unwrap();
+        let mut cpu = CPU::new(bus);
+        cpu.pc = 0xF000;
+        cpu.cycle_stepped = cycle_stepped;
+        cpu.execute().unwrap();
+        let e = cpu.execute().unwrap_err();
+        assert!(matches!(
+            e.kind,
+            ErrorKind::Unmapped(UnmappedAccess {
+                addr: 0x2000,
+                write: true
+            })
+        ));
+        assert_eq!(e.pc, 0xF002);
+        assert_eq!(
+            e.to_string().split(", PC:").next(),
+            Some("Write to unmapped address 0x2000. At $F002 (STA $2000)")
+        );
+        // Reported once
+        cpu.execute().unwrap();
+    }
+
+    // The same for interrupts, with nothing at the vectors
+    let bus = MemoryMap::builder()
+        .ram(0x0000..=0x07FF)
+        .unmapped(Unmapped::Error)
+        .build()
+        .unwrap();
+    let mut cpu = CPU::new(bus);
+    cpu.pc = 0x0200;
+    cpu.set_nmi(true);
+    let e = cpu.execute().unwrap_err();
+    assert!(matches!(
+        e.kind,
+        ErrorKind::Unmapped(UnmappedAccess {
+            addr: 0xFFFA,
+            write: false
+        })
+    ));
+    assert_eq!(e.pc, 0x0200);
+}
+
+#[test]
+fn lists_the_map() {
+    let mut bus = MemoryMap::builder()
+        .rom(0xC000..=0xFFFF, [0xFF; 0x100])
+        .device(0x2000..=0x2007, Latch::default())
+        .ram(0x0000..=0x07FF)
+        .mirror(0x0800..=0x1FFF, 0x0000..=0x07FF)
+        .build()
+        .unwrap();
+    assert_eq!(
+        bus.to_string(),
+        "$0000-$07FF RAM (2048 bytes)\n\
+         $0800-$1FFF mirror of $0000-$07FF\n\
+         $2000-$2007 Latch\n\
+         $C000-$FFFF ROM (256 bytes)\n\
+         Unmapped: OpenBus"
+    );
+    bus.load(&[1, 2, 3], 0xC0FF);
+    assert_eq!(bus.peek(0xC0FF), Some(1));
+    assert_eq!(bus.peek(0xC000), Some(2));
+    assert_eq!(bus.peek(0xFF01), Some(3));
+}
+
+#[test]
+fn rom_offsets_follow_the_image() {
+    let bus = MemoryMap::builder()
+        .ram(0x0000..=0x07FF)
+        .rom(0x8000..=0xBFFF, [0; 0x1000])
+        .mirror(0xC000..=0xCFFF, 0x8000..=0x8FFF)
+        .rom(0xE000..=0xFFFF, [0; 0x2000])
+        .build()
+        .unwrap();
+    assert_eq!(bus.rom_offset(0x0010), None);
+    assert_eq!(bus.rom_offset(0x8010), Some(0x10));
+    // The first ROM repeats over its region.
+    assert_eq!(bus.rom_offset(0x9010), Some(0x10));
+    assert_eq!(bus.rom_offset(0xC020), Some(0x20));
+    // The second ROM comes after the first in the image.
+    assert_eq!(bus.rom_offset(0xE000), Some(0x1000));
+}
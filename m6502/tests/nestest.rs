@@ -0,0 +1,95 @@
+//! Runs nestest in automation mode and compares a trace of every instruction against the
+//! reference log, in the Nintendulator format it is written in.
+//!
+//! See `fixtures/README.md` for where the ROM and log come from.
+
+use m6502::{
+    bus::Bus,
+    trace::{TraceEntry, TraceFormat, Tracer},
+    variant::Variant,
+    CPU,
+};
+
+/// Where nestest starts when run without a PPU
+const AUTOMATION_START: u16 = 0xC000;
+/// Cycles taken by the RESET sequence before the first instruction
+const START_CYCLES: u64 = 7;
+
+/// The CPU side of an NES with an NROM cartridge: 2K of RAM mirrored up to $1FFF, and 16K or
+/// 32K of PRG ROM at $8000. The PPU and APU aren't emulated; their registers read as $FF.
+struct Nes {
+    ram: [u8; 0x800],
+    prg_rom: Vec<u8>,
+}
+
+impl Nes {
+    fn from_ines(image: &[u8]) -> Self {
+        assert_eq!(&image[..4], b"NES\x1A", "not an iNES image");
+        let prg_size = image[4] as usize * 0x4000;
+        Self {
+            ram: [0; 0x800],
+            prg_rom: image[16..16 + prg_size].to_vec(),
+        }
+    }
+}
+
+impl Bus for Nes {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.peek(addr)
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        match addr {
+            0x0000..=0x1FFF => Some(self.ram[addr as usize % 0x800]),
+            0x2000..=0x401F => Some(0xFF),
+            0x8000..=0xFFFF => Some(self.prg_rom[(addr as usize - 0x8000) % self.prg_rom.len()]),
+            _ => None,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        if let 0x0000..=0x1FFF = addr {
+            self.ram[addr as usize % 0x800] = data;
+        }
+    }
+}
+
+#[test]
+fn nestest() {
+    let nes = Nes::from_ines(include_bytes!("fixtures/nestest.nes"));
+    let mut cpu = CPU::with_variant(nes, Variant::Ricoh2A03);
+    cpu.undocumented_opcodes = true;
+    cpu.pc = AUTOMATION_START;
+    cpu.stack_pointer = 0xFD;
+    cpu.status.byte = 0x24;
+    cpu.total_cycles = START_CYCLES;
+    cpu.tracer = Some(Tracer::ring_buffer(usize::MAX));
+
+    let log = include_str!("fixtures/nestest.log");
+    let mut previous = "";
+    for (number, expected) in log.lines().enumerate() {
+        let actual = TraceEntry::capture(&cpu).nintendulator();
+        if actual != expected {
+            let column = actual
+                .chars()
+                .zip(expected.chars())
+                .take_while(|(a, e)| a == e)
+                .count();
+            panic!(
+                "line {} differs:\n  previous {previous}\n  expected {expected}\n  actual   {actual}\n           {:>width$}",
+                number + 1,
+                "^",
+                width = column + 1
+            );
+        }
+        cpu.execute()
+            .unwrap_or_else(|e| panic!("line {}: {e}", number + 1));
+        previous = expected;
+    }
+    // The tracer saw the same instructions.
+    let tracer = cpu.tracer.take().unwrap();
+    let exported = String::from_utf8(tracer.export(TraceFormat::Nintendulator)).unwrap();
+    assert!(exported.lines().eq(log.lines()));
+    // nestest leaves the number of the first failed official and undocumented test here.
+    assert_eq!((cpu.peek(0x0002), cpu.peek(0x0003)), (0, 0));
+}
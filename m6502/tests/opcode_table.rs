@@ -0,0 +1,160 @@
+//! What the opcode tables tell about each opcode.
+
+use m6502::{
+    addressing_modes::{Access, AddressingMode},
+    instructions::Instruction,
+    opcode_table::{
+        decode, encode, OpcodeEntry, CMOS_OPCODE_TABLE, OPCODE_TABLE, UNDOCUMENTED_OPCODE_TABLE,
+    },
+    status::Flags,
+    variant::Variant,
+};
+
+#[test]
+fn lengths_match_addressing_modes() {
+    let tables = [OPCODE_TABLE, UNDOCUMENTED_OPCODE_TABLE, CMOS_OPCODE_TABLE];
+    for entry in tables.iter().flatten().flatten() {
+        let operand = match entry.addressing_mode {
+            AddressingMode::Implied => 0,
+            AddressingMode::Absolute
+            | AddressingMode::AbsoluteX
+            | AddressingMode::AbsoluteY
+            | AddressingMode::Indirect
+            | AddressingMode::AbsoluteIndirectX
+            | AddressingMode::ZeroPageRelative => 2,
+            _ => 1,
+        };
+        assert_eq!(entry.bytes, 1 + operand, "{:#04X}", entry.code);
+    }
+}
+
+#[test]
+fn official_opcodes() {
+    let count = |table: &[Option<OpcodeEntry>; 256], official| {
+        table
+            .iter()
+            .flatten()
+            .filter(|entry| entry.official == official)
+            .count()
+    };
+    assert_eq!(count(&OPCODE_TABLE, true), 151);
+    assert_eq!(count(&UNDOCUMENTED_OPCODE_TABLE, false), 105);
+    // The WDC 65C02, Rockwell bit instructions included
+    assert_eq!(count(&CMOS_OPCODE_TABLE, true), 212);
+    assert_eq!(count(&CMOS_OPCODE_TABLE, false), 44);
+}
+
+#[test]
+fn flags_read_and_written() {
+    let nmos = |opcode| decode(Variant::Nmos, true, opcode).unwrap();
+    let cmos = |opcode| decode(Variant::Cmos65C02, false, opcode).unwrap();
+    let nz = Flags::NEGATIVE | Flags::ZERO;
+
+    // LDA #
+    assert_eq!(nmos(0xA9).flags_read, Flags::NONE);
+    assert_eq!(nmos(0xA9).flags_written, nz);
+    // ADC #
+    assert_eq!(nmos(0x69).flags_read, Flags::CARRY | Flags::DECIMAL);
+    assert_eq!(nmos(0x69).flags_written.to_string(), "NVZC");
+    // BNE
+    assert_eq!(nmos(0xD0).flags_read, Flags::ZERO);
+    assert!(nmos(0xD0).flags_written.is_empty());
+    // PLP and PHP
+    assert_eq!(nmos(0x28).flags_written, Flags::ALL);
+    assert_eq!(nmos(0x08).flags_read, Flags::ALL);
+    // BIT $12, and BIT # which only has Z to set
+    assert_eq!(cmos(0x24).flags_written.to_string(), "NVZ");
+    assert_eq!(cmos(0x89).flags_written, Flags::ZERO);
+    // BRK also clears D on the 65C02.
+    assert_eq!(nmos(0x00).flags_written, Flags::INTERRUPT_DISABLE);
+    assert!(cmos(0x00).flags_written.contains(Flags::DECIMAL));
+    // DCP $12
+    assert_eq!(nmos(0xC7).flags_written.to_string(), "NZC");
+}
+
+#[test]
+fn access_classes() {
+    let nmos = |opcode| decode(Variant::Nmos, true, opcode).unwrap().access;
+    let cmos = |opcode| decode(Variant::Cmos65C02, false, opcode).unwrap().access;
+
+    assert_eq!(nmos(0xAD), Access::Read); // LDA $1234
+    assert_eq!(nmos(0x8D), Access::Write); // STA $1234
+    assert_eq!(nmos(0xEE), Access::ReadModifyWrite); // INC $1234
+    assert_eq!(nmos(0xCF), Access::ReadModifyWrite); // DCP $1234
+    assert_eq!(nmos(0x48), Access::Stack); // PHA
+    assert_eq!(nmos(0xF0), Access::Branch); // BEQ
+    assert_eq!(nmos(0x20), Access::Jump); // JSR
+    assert_eq!(nmos(0x40), Access::Jump); // RTI
+    assert_eq!(cmos(0x9C), Access::Write); // STZ $1234
+    assert_eq!(cmos(0x8F), Access::Branch); // BBS0
+    assert_eq!(cmos(0xDA), Access::Stack); // PHX
+}
+
+#[test]
+fn encodes_what_decodes() {
+    for variant in [Variant::Nmos, Variant::Cmos65C02] {
+        for undocumented in [false, true] {
+            for opcode in 0..=255 {
+                let Some(entry) = decode(variant, undocumented, opcode) else {
+                    continue;
+                };
+                let encoded = encode(
+                    variant,
+                    undocumented,
+                    entry.instruction,
+                    entry.addressing_mode,
+                )
+                .unwrap();
+                assert_eq!(encoded.instruction, entry.instruction);
+                assert_eq!(encoded.addressing_mode, entry.addressing_mode);
+                // Official first, then the lowest
+                assert!(encoded.official >= entry.official);
+                if encoded.official == entry.official {
+                    assert!(encoded.code <= entry.code);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn encodes_official_opcodes_first() {
+    let encode_nmos =
+        |instruction, mode| encode(Variant::Nmos, true, instruction, mode).unwrap().code;
+    assert_eq!(encode_nmos(Instruction::NOP, AddressingMode::Implied), 0xEA);
+    assert_eq!(
+        encode_nmos(Instruction::SBC, AddressingMode::Immediate),
+        0xE9
+    );
+    // The lowest of the undocumented ones, when there is no other
+    assert_eq!(
+        encode_nmos(Instruction::NOP, AddressingMode::Immediate),
+        0x80
+    );
+    assert_eq!(encode_nmos(Instruction::JAM, AddressingMode::Implied), 0x02);
+
+    assert_eq!(
+        encode(
+            Variant::Cmos65C02,
+            false,
+            Instruction::NOP,
+            AddressingMode::Implied
+        )
+        .map(|entry| entry.code),
+        Some(0xEA)
+    );
+    assert!(encode(
+        Variant::Nmos,
+        false,
+        Instruction::LAX,
+        AddressingMode::ZeroPage
+    )
+    .is_none());
+    assert!(encode(
+        Variant::Nmos,
+        true,
+        Instruction::STZ,
+        AddressingMode::ZeroPage
+    )
+    .is_none());
+}
@@ -0,0 +1,205 @@
+//! Counting cycles per address and per subroutine.
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::{cpu_on, in_both_modes, ram};
+use m6502::{
+    bus::Ram,
+    profiler::{AddressProfile, Profiler, SortBy, SubroutineProfile},
+    variant::Variant,
+    CPU,
+};
+
+fn cpu(program: &[(u16, &[u8])], cycle_stepped: bool) -> CPU<Ram> {
+    let mut cpu = cpu_on(ram(program), Variant::Nmos, cycle_stepped);
+    cpu.profiler = Some(Profiler::new());
+    cpu
+}
+
+/// Calls `outer` twice, which calls `inner`.
+const NESTED: &[(u16, &[u8])] = &[
+    (
+        0x0600,
+        &[
+            0x20, 0x10, 0x06, // JSR outer
+            0x20, 0x10, 0x06, // JSR outer
+            0x4C, 0x06, 0x06, // JMP *
+        ],
+    ),
+    // outer
+    (
+        0x0610,
+        &[
+            0x20, 0x20, 0x06, // JSR inner
+            0x60, // RTS
+        ],
+    ),
+    // inner
+    (
+        0x0620,
+        &[
+            0xEA, // NOP
+            0x60, // RTS
+        ],
+    ),
+];
+
+#[test]
+fn attributes_cycles_to_subroutines() {
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(NESTED, cycle_stepped);
+        for _ in 0..12 {
+            cpu.execute().unwrap();
+        }
+        let profiler = cpu.profiler.as_ref().unwrap();
+        // JSR and RTS take 6 cycles, NOP 2 and JMP 3.
+        assert_eq!(profiler.total_cycles(), 6 + 20 + 6 + 20 + 3);
+        assert_eq!(profiler.instructions(), 11);
+        assert_eq!(
+            profiler.subroutine(0x0610),
+            Some(&SubroutineProfile {
+                calls: 2,
+                inclusive_cycles: 40,
+                exclusive_cycles: 24
+            })
+        );
+        assert_eq!(
+            profiler.subroutine(0x0620),
+            Some(&SubroutineProfile {
+                calls: 2,
+                inclusive_cycles: 16,
+                exclusive_cycles: 16
+            })
+        );
+        assert_eq!(
+            profiler.address(0x0621),
+            Some(&AddressProfile {
+                executions: 2,
+                cycles: 12
+            })
+        );
+        let by_count: Vec<_> = profiler
+            .addresses(SortBy::Count)
+            .iter()
+            .map(|(addr, _)| *addr)
+            .collect();
+        assert_eq!(by_count[4..], [0x0600, 0x0603, 0x0606]);
+    });
+}
+
+#[test]
+fn stacks_can_wrap_around() {
+    in_both_modes(|cycle_stepped| {
+        let mut cpu = cpu(NESTED, cycle_stepped);
+        // Calling inner wraps the stack pointer around, from outer's $01 to $FF.
+        cpu.stack_pointer = 0x03;
+        // PHA, PLA, RTS instead of inner's NOP
+        cpu.bus.load(&[0x48, 0x68, 0x60], 0x0620);
+        for _ in 0..7 {
+            cpu.execute().unwrap();
+        }
+        let profiler = cpu.profiler.as_ref().unwrap();
+        // Inner runs PHA (3 cycles), PLA (4) and RTS (6), all inside the call.
+        assert_eq!(
+            profiler.subroutine(0x0620),
+            Some(&SubroutineProfile {
+                calls: 1,
+                inclusive_cycles: 13,
+                exclusive_cycles: 13
+            })
+        );
+        assert_eq!(profiler.subroutine(0x0610).unwrap().inclusive_cycles, 25);
+    });
+}
+
+#[test]
+fn txs_leaves_all_subroutines() {
+    let mut cpu = cpu(
+        &[
+            (0x0600, &[0x20, 0x10, 0x06]), // JSR sub
+            (
+                0x0610,
+                &[
+                    0xA2, 0xF0, // LDX #$F0
+                    0x9A, // TXS
+                    0x4C, 0x30, 0x06, // JMP $0630
+                ],
+            ),
+            (0x0630, &[0x4C, 0x30, 0x06]), // JMP *
+        ],
+        false,
+    );
+    for _ in 0..6 {
+        cpu.execute().unwrap();
+    }
+    let profiler = cpu.profiler.as_ref().unwrap();
+    // LDX and TXS take 2 cycles each. The jumps after it don't count.
+    assert_eq!(
+        profiler.subroutine(0x0610),
+        Some(&SubroutineProfile {
+            calls: 1,
+            inclusive_cycles: 4,
+            exclusive_cycles: 4
+        })
+    );
+}
+
+#[test]
+fn interrupt_handlers_are_subroutines() {
+    let mut cpu = cpu(
+        &[
+            (0x0600, &[0x4C, 0x00, 0x06]), // JMP *
+            (0x0700, &[0x40]),             // RTI
+            (0xFFFA, &[0x00, 0x07]),
+        ],
+        false,
+    );
+    cpu.execute().unwrap();
+    cpu.set_nmi(true);
+    for _ in 0..4 {
+        cpu.execute().unwrap();
+    }
+    let profiler = cpu.profiler.as_ref().unwrap();
+    // The 7 cycles of the interrupt sequence and the 6 of RTI
+    assert_eq!(
+        profiler.subroutine(0x0700),
+        Some(&SubroutineProfile {
+            calls: 1,
+            inclusive_cycles: 13,
+            exclusive_cycles: 13
+        })
+    );
+    assert_eq!(profiler.address(0x0600).unwrap().executions, 2);
+}
+
+#[test]
+fn reports_as_text_and_json() {
+    let mut cpu = cpu(NESTED, false);
+    for _ in 0..12 {
+        cpu.execute().unwrap();
+    }
+    let labels = HashMap::from([(0x0610, "outer".to_string())]);
+    let profiler = cpu.profiler.as_ref().unwrap();
+    // Ties keep address order.
+    let report = profiler.report(SortBy::InclusiveCycles, 1, &labels);
+    assert_eq!(
+        report,
+        "55 cycles, 11 instructions\n\
+         \n\
+         Subroutine            Calls    Inclusive       %    Exclusive       %\n\
+         outer                     2           40  72.73%           24  43.64%\n\
+         \n\
+         Address          Executions       Cycles       %\n\
+         outer                     2           12  21.82%\n"
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&profiler.to_json(SortBy::Address, &labels)).unwrap();
+    assert_eq!(json["total_cycles"], 55);
+    assert_eq!(json["subroutines"][0]["label"], "outer");
+    assert_eq!(json["subroutines"][1]["address"], 0x0620);
+    assert_eq!(json["subroutines"][1]["exclusive_cycles"], 16);
+    assert_eq!(json["addresses"].as_array().unwrap().len(), 7);
+}
@@ -0,0 +1,102 @@
+//! Running cycles in batches, compared with ticking them one by one.
+
+mod common;
+
+use common::{cpu_on, in_both_modes, ram};
+use m6502::{
+    breakpoints::BreakpointKind,
+    bus::{Bus, Ram},
+    variant::Variant,
+    CPU,
+};
+
+/// RAM that counts how many cycles it was ticked for
+struct Counted {
+    ram: Ram,
+    ticks: u64,
+}
+
+impl Bus for Counted {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.ram.read(addr)
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        self.ram.peek(addr)
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        self.ram.write(addr, data)
+    }
+
+    fn tick(&mut self) {
+        self.ticks += 1;
+    }
+}
+
+fn cpu(cycle_stepped: bool) -> CPU<Counted> {
+    let ram = ram(&[(0x0000, include_bytes!("fixtures/6502_functional_test.bin"))]);
+    let mut cpu = cpu_on(Counted { ram, ticks: 0 }, Variant::Nmos, cycle_stepped);
+    cpu.pc = 0x0400;
+    cpu
+}
+
+fn state(cpu: &CPU<Counted>) -> (String, u8, u64, u64) {
+    (
+        cpu.registers().to_string(),
+        cpu.cycles_left,
+        cpu.total_cycles,
+        cpu.bus.ticks,
+    )
+}
+
+#[test]
+fn matches_ticking() {
+    in_both_modes(|cycle_stepped| {
+        let mut ticked = cpu(cycle_stepped);
+        let mut batched = cpu(cycle_stepped);
+        // Batches of 1 to 13 cycles end in the middle of most instructions.
+        for batch in (1..=13).cycle().take(20_000) {
+            for _ in 0..batch {
+                ticked.tick().unwrap();
+            }
+            batched.run_cycles(batch).unwrap();
+            assert_eq!(state(&batched), state(&ticked));
+        }
+        // An NMI in the middle of a batch is served between two instructions, as when ticking.
+        ticked.set_nmi(true);
+        batched.set_nmi(true);
+        for _ in 0..1000 {
+            ticked.tick().unwrap();
+        }
+        batched.run_cycles(1000).unwrap();
+        assert_eq!(state(&batched), state(&ticked));
+    });
+}
+
+#[test]
+fn stops_at_breakpoints_like_ticking() {
+    for kind in [BreakpointKind::Execute, BreakpointKind::Write] {
+        let mut ticked = cpu(false);
+        let mut batched = cpu(false);
+        let address = if kind == BreakpointKind::Execute {
+            // Wherever the test gets to after a while
+            let mut cpu = cpu(false);
+            cpu.run_cycles(5000).unwrap();
+            cpu.pc
+        } else {
+            // Where the functional test keeps the number of the test in progress
+            0x0200
+        };
+        ticked.breakpoints.add(kind, address..=address);
+        batched.breakpoints.add(kind, address..=address);
+
+        let hit = loop {
+            if let Some(hit) = ticked.tick().unwrap() {
+                break hit;
+            }
+        };
+        assert_eq!(batched.run_cycles(1_000_000).unwrap(), Some(hit));
+        assert_eq!(state(&batched), state(&ticked));
+    }
+}
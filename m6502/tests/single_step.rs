@@ -0,0 +1,242 @@
+//! Runs vectors in the format of the SingleStepTests (formerly ProcessorTests) for the NMOS
+//! 6502: each vector gives the registers and RAM before and after one instruction, and every bus
+//! access it makes.
+//!
+//! The suite is too big to keep here. Point `SINGLE_STEP_TESTS` at a directory of its per-opcode
+//! files (`00.json` to `ff.json`, from <https://github.com/SingleStepTests/65x02/tree/main/6502/v1>)
+//! and run `cargo test -p m6502 --test single_step -- --ignored`:
+//!
+//! - `full_suite` runs all of it, for the documented and undocumented opcodes but JAM. The pass
+//!   rate and first mismatch of every opcode are printed; run with `--nocapture` to see them when
+//!   everything passes.
+//! - `write_sample` copies the first [`SAMPLE`] vectors of every opcode into
+//!   `fixtures/single_step`, for `smoke_vectors` to run on every `cargo test`. Until it has been
+//!   run there, the fixtures are only a few hand-written vectors.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use m6502::{bus::Bus, instructions::Instruction, opcode_table::decode, variant::Variant, CPU};
+use serde::{Deserialize, Serialize};
+
+/// Vectors of each opcode that [`write_sample`] keeps
+const SAMPLE: usize = 10;
+
+#[derive(Deserialize, Serialize)]
+struct TestCase {
+    name: String,
+    initial: State,
+    #[serde(rename = "final")]
+    expected: State,
+    cycles: Vec<(u16, u8, String)>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct State {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+/// 64K of RAM that logs every access in the format of the vectors
+struct RecordingRam {
+    memory: Vec<u8>,
+    accesses: Vec<(u16, u8, String)>,
+}
+
+impl Bus for RecordingRam {
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        let data = self.memory[addr as usize];
+        self.accesses.push((addr, data, "read".into()));
+        Some(data)
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        Some(self.memory[addr as usize])
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        self.memory[addr as usize] = data;
+        self.accesses.push((addr, data, "write".into()));
+    }
+}
+
+/// Names of the status flags that differ between two status registers
+fn flag_difference(actual: u8, expected: u8) -> String {
+    "NV-BDIZC"
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| (actual ^ expected) & (0x80 >> i) != 0)
+        .map(|(_, flag)| flag)
+        .collect()
+}
+
+/// Runs one vector, describing the first mismatch if it fails.
+fn run(case: &TestCase) -> Result<(), String> {
+    let mut memory = vec![0; 0x10000];
+    for &(addr, value) in &case.initial.ram {
+        memory[addr as usize] = value;
+    }
+    let mut cpu = CPU::new(RecordingRam {
+        memory,
+        accesses: Vec::new(),
+    });
+    cpu.undocumented_opcodes = true;
+    cpu.pc = case.initial.pc;
+    cpu.stack_pointer = case.initial.s;
+    cpu.ac = case.initial.a;
+    cpu.x = case.initial.x;
+    cpu.y = case.initial.y;
+    cpu.status.byte = case.initial.p;
+    cpu.execute().map_err(|e| e.to_string())?;
+
+    let expected = &case.expected;
+    let registers = [
+        ("PC", cpu.pc, expected.pc),
+        ("S", cpu.stack_pointer as u16, expected.s as u16),
+        ("A", cpu.ac as u16, expected.a as u16),
+        ("X", cpu.x as u16, expected.x as u16),
+        ("Y", cpu.y as u16, expected.y as u16),
+    ];
+    for (name, actual, expected) in registers {
+        if actual != expected {
+            return Err(format!("{name} is {actual:#x}, expected {expected:#x}"));
+        }
+    }
+    if cpu.status.byte != expected.p {
+        return Err(format!(
+            "P is {:#04x}, expected {:#04x} (flags {})",
+            cpu.status.byte,
+            expected.p,
+            flag_difference(cpu.status.byte, expected.p)
+        ));
+    }
+    for &(addr, value) in &expected.ram {
+        let actual = cpu.bus.memory[addr as usize];
+        if actual != value {
+            return Err(format!(
+                "memory at {addr:#06x} is {actual:#04x}, expected {value:#04x}"
+            ));
+        }
+    }
+    let accesses = &cpu.bus.accesses;
+    for (cycle, expected) in case.cycles.iter().enumerate() {
+        match accesses.get(cycle) {
+            Some(actual) if actual == expected => {}
+            actual => {
+                return Err(format!(
+                    "cycle {cycle} is {actual:?}, expected {expected:?}"
+                ))
+            }
+        }
+    }
+    if accesses.len() != case.cycles.len() {
+        return Err(format!(
+            "took {} cycles, expected {}",
+            accesses.len(),
+            case.cycles.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Runs the vectors of every opcode with a file in `dir`. Returns the number of files found.
+fn run_files(dir: &Path) -> usize {
+    let mut files = 0;
+    let mut total = 0;
+    let mut failed = 0;
+    for opcode in 0..=255 {
+        let Some(entry) = decode(Variant::Nmos, true, opcode) else {
+            continue;
+        };
+        // The vectors expect a jammed CPU to keep reading, where this one halts.
+        if entry.instruction == Instruction::JAM {
+            continue;
+        }
+        let Ok(json) = fs::read_to_string(dir.join(format!("{opcode:02x}.json"))) else {
+            continue;
+        };
+        files += 1;
+        let cases: Vec<TestCase> = serde_json::from_str(&json).expect("malformed test file");
+        let mut passed = 0;
+        let mut first_failure = None;
+        for case in &cases {
+            match run(case) {
+                Ok(()) => passed += 1,
+                Err(e) => {
+                    first_failure
+                        .get_or_insert_with(|| format!(", first failure: [{}] {e}", case.name));
+                }
+            }
+        }
+        println!(
+            "{opcode:02x} {:?} {:?}: {passed}/{} passed{}",
+            entry.instruction,
+            entry.addressing_mode,
+            cases.len(),
+            first_failure.unwrap_or_default()
+        );
+        total += cases.len();
+        failed += cases.len() - passed;
+    }
+    assert!(total > 0, "no test files found in {}", dir.display());
+    assert_eq!(failed, 0, "{failed} of {total} vectors failed");
+    files
+}
+
+/// Where the sample of the suite is kept
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/single_step")
+}
+
+/// The directory of the full suite
+fn suite() -> PathBuf {
+    env::var_os("SINGLE_STEP_TESTS")
+        .map(PathBuf::from)
+        .expect("SINGLE_STEP_TESTS isn't set to the directory of the suite's 6502 files")
+}
+
+#[test]
+fn smoke_vectors() {
+    run_files(&fixtures());
+}
+
+#[test]
+#[ignore = "needs the SingleStepTests files, see SINGLE_STEP_TESTS"]
+fn full_suite() {
+    let opcodes = (0..=255)
+        .filter_map(|opcode| decode(Variant::Nmos, true, opcode))
+        .filter(|entry| entry.instruction != Instruction::JAM)
+        .count();
+    assert_eq!(run_files(&suite()), opcodes, "some opcodes have no file");
+}
+
+#[test]
+#[ignore = "needs the SingleStepTests files, see SINGLE_STEP_TESTS"]
+fn write_sample() {
+    let (suite, fixtures) = (suite(), fixtures());
+    for opcode in 0..=255u8 {
+        let name = format!("{opcode:02x}.json");
+        let Ok(json) = fs::read_to_string(suite.join(&name)) else {
+            continue;
+        };
+        let cases: Vec<TestCase> = serde_json::from_str(&json).expect("malformed test file");
+        // One vector per line, like the suite's files
+        let lines: Vec<_> = cases
+            .iter()
+            .take(SAMPLE)
+            .map(|case| serde_json::to_string(case).unwrap())
+            .collect();
+        fs::write(
+            fixtures.join(name),
+            format!("[\n{}\n]\n", lines.join(",\n")),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+//! Saving a snapshot, carrying on, and checking that restoring it replays the same run.
+
+use m6502::{
+    bus::{Bus, Ram},
+    snapshot::{Snapshot, SnapshotError, SNAPSHOT_VERSION},
+    CPU,
+};
+
+/// Counts down from $FF in zero page $10 and stores each value at $0200,X, forever.
+const PROGRAM: &[u8] = &[
+    0xA9, 0xFF, // LDA #$FF
+    0x85, 0x10, // STA $10
+    0xA6, 0x10, // LDX $10
+    0x8A, // TXA
+    0x9D, 0x00, 0x02, // STA $0200,X
+    0xC6, 0x10, // DEC $10
+    0x4C, 0x04, 0x06, // JMP $0604
+];
+
+fn cpu() -> CPU<Ram> {
+    let mut ram = Ram::new();
+    ram.load(PROGRAM, 0x0600);
+    let mut cpu = CPU::new(ram);
+    cpu.pc = 0x0600;
+    cpu.cycle_stepped = true;
+    cpu
+}
+
+fn state(cpu: &CPU<Ram>) -> (String, u8, Vec<u8>) {
+    (
+        cpu.registers().to_string(),
+        cpu.data_bus,
+        cpu.bus.save_state().unwrap(),
+    )
+}
+
+#[test]
+fn restoring_replays_the_same_run() {
+    let mut cpu = cpu();
+    // Stop in the middle of an instruction.
+    for _ in 0..1001 {
+        cpu.tick().unwrap();
+    }
+    let bytes = cpu.snapshot().unwrap().to_bytes();
+    for _ in 0..500 {
+        cpu.tick().unwrap();
+    }
+    let expected = state(&cpu);
+
+    let mut restored = CPU::new(Ram::new());
+    restored
+        .restore(&Snapshot::from_bytes(&bytes).unwrap())
+        .unwrap();
+    assert!(restored.cycle_stepped);
+    for _ in 0..500 {
+        restored.tick().unwrap();
+    }
+    assert_eq!(state(&restored), expected);
+    assert_eq!(restored.total_cycles, cpu.total_cycles);
+}
+
+#[test]
+fn rejects_bad_snapshots() {
+    let bytes = cpu().snapshot().unwrap().to_bytes();
+    assert!(matches!(
+        Snapshot::from_bytes(b"not a snapshot"),
+        Err(SnapshotError::NotASnapshot)
+    ));
+    let mut newer = bytes.clone();
+    newer[8..10].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        Snapshot::from_bytes(&newer),
+        Err(SnapshotError::Version(v)) if v == SNAPSHOT_VERSION + 1
+    ));
+    assert!(matches!(
+        Snapshot::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(SnapshotError::Truncated)
+    ));
+
+    let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+    let mut small = CPU::new(Ram::of_size(0x800));
+    assert!(matches!(
+        small.restore(&snapshot),
+        Err(SnapshotError::Invalid(_))
+    ));
+    assert_eq!(small.pc, 0);
+}
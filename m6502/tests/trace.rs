@@ -0,0 +1,85 @@
+//! Recording traces, in either stepping mode, with start and stop conditions.
+
+use m6502::{
+    bus::Ram,
+    trace::{TraceCondition, TraceFormat, Tracer, TRACE_RECORD_SIZE},
+    CPU,
+};
+
+/// Counts X up forever.
+const PROGRAM: &[u8] = &[
+    0xA2, 0x00, // LDX #$00
+    0xE8, // INX
+    0x86, 0x10, // STX $10
+    0x4C, 0x02, 0x06, // JMP $0602
+];
+
+fn cpu(tracer: Tracer) -> CPU<Ram> {
+    let mut ram = Ram::new();
+    ram.load(PROGRAM, 0x0600);
+    let mut cpu = CPU::new(ram);
+    cpu.pc = 0x0600;
+    cpu.tracer = Some(tracer);
+    cpu
+}
+
+fn pcs(cpu: &CPU<Ram>) -> Vec<u16> {
+    let tracer = cpu.tracer.as_ref().unwrap();
+    tracer.entries().map(|entry| entry.registers.pc).collect()
+}
+
+#[test]
+fn keeps_the_last_entries() {
+    let mut cpu = cpu(Tracer::ring_buffer(4));
+    for _ in 0..10 {
+        cpu.execute().unwrap();
+    }
+    assert_eq!(pcs(&cpu), [0x0605, 0x0602, 0x0603, 0x0605]);
+    let entry = cpu.tracer.as_ref().unwrap().entries().nth(2).unwrap();
+    assert_eq!(entry.effective_address, Some(0x0010));
+    assert_eq!(entry.value, 2);
+    assert_eq!(
+        entry.mesen(),
+        "0603  STX $10 = $02                            A:00 X:03 Y:00 S:00 P:nv-bdizc Cycle:20"
+    );
+}
+
+#[test]
+fn cycle_stepping_traces_the_same_instructions() {
+    let mut cpu = cpu(Tracer::ring_buffer(100));
+    cpu.cycle_stepped = true;
+    for _ in 0..24 {
+        cpu.tick().unwrap();
+    }
+    // The JMP has started but not finished.
+    assert_eq!(
+        pcs(&cpu),
+        [0x0600, 0x0602, 0x0603, 0x0605, 0x0602, 0x0603, 0x0605, 0x0602, 0x0603, 0x0605]
+    );
+}
+
+#[test]
+fn starts_and_stops_on_conditions() {
+    let tracer = Tracer::ring_buffer(100)
+        .starting_at(TraceCondition::Pc(0x0605))
+        .stopping_at(TraceCondition::Cycle(20));
+    let mut cpu = cpu(tracer);
+    for _ in 0..20 {
+        cpu.execute().unwrap();
+    }
+    assert_eq!(pcs(&cpu), [0x0605, 0x0602, 0x0603, 0x0605, 0x0602]);
+    assert!(!cpu.tracer.as_ref().unwrap().is_recording());
+}
+
+#[test]
+fn exports_binary_records() {
+    let mut cpu = cpu(Tracer::ring_buffer(2));
+    cpu.execute().unwrap();
+    cpu.execute().unwrap();
+    let binary = cpu.tracer.as_ref().unwrap().export(TraceFormat::Binary);
+    assert_eq!(binary.len(), 2 * TRACE_RECORD_SIZE);
+    assert_eq!(
+        binary[TRACE_RECORD_SIZE..],
+        [0x02, 0x06, 0x00, 0x00, 0x00, 0x00, 0x22, 0xE8, 0x86, 0x10, 2, 0, 0, 0, 0, 0, 0, 0]
+    );
+}
@@ -1,10 +1,27 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
 use egui::Ui;
-use m6502::{bus::Bus, CPUError, CPU};
+use m6502::{
+    breakpoints::{Break, BreakpointKind},
+    bus::Bus,
+    profiler::{Profiler, SortBy},
+    snapshot::Snapshot,
+    CPUError, Halt, CPU,
+};
+
+/// Frames the profiler report is shown for before it's rebuilt, since sorting it every frame
+/// would slow the emulator down
+const PROFILE_REFRESH_FRAMES: u32 = 30;
 
 #[derive(Debug)]
 pub enum HarnessState {
     Paused,
     Running,
+    /// Stopped by a breakpoint, and can be resumed
+    Break(Break),
+    /// The program stopped the CPU, which has to be reset
+    Halted(Halt),
+    /// The emulator failed
     Error(CPUError),
 }
 
@@ -30,6 +47,20 @@ pub struct Harness<T: Bus + Clone> {
     pub cpu: CPU<T>,
     pub frequency: u32,
     pub state: HarnessState,
+    /// File the snapshot buttons save to and load from
+    pub snapshot_path: PathBuf,
+    /// Outcome of the last snapshot operation
+    pub message: Option<String>,
+    /// The breakpoint being added
+    new_breakpoint: (BreakpointKind, u16, u16),
+    /// Names of addresses in the program, for the profiler report
+    pub labels: BTreeMap<u16, String>,
+    /// Order of the profiler report
+    profile_sort: SortBy,
+    /// The top of the profiler report as last built
+    profile_report: String,
+    /// Frames left until the profiler report is rebuilt
+    profile_refresh: u32,
 }
 
 impl<T: Bus + Clone> Harness<T> {
@@ -39,21 +70,26 @@ impl<T: Bus + Clone> Harness<T> {
             cpu,
             frequency: 60,
             state: HarnessState::Paused,
+            snapshot_path: PathBuf::from("snapshot.m6502"),
+            message: None,
+            new_breakpoint: (BreakpointKind::Execute, 0, 0),
+            labels: BTreeMap::new(),
+            profile_sort: SortBy::default(),
+            profile_report: String::new(),
+            profile_refresh: 0,
         }
     }
 
     pub fn render(&mut self, ui: &mut Ui) {
-        if !self.state.is_error() {
-            ui.label(format!("CPU is {:#?}", self.state));
-        } else {
-            ui.label(format!(
-                "CPU has encountered an error: {}",
-                self.state.error().unwrap()
-            ));
-        }
+        match &self.state {
+            HarnessState::Error(e) => ui.label(format!("CPU has encountered an error: {e}")),
+            HarnessState::Break(hit) => ui.label(format!("CPU is stopped: {hit}")),
+            HarnessState::Halted(halt) => ui.label(format!("Program has halted: {halt}")),
+            state => ui.label(format!("CPU is {state:#?}")),
+        };
 
         match self.state {
-            HarnessState::Paused => {
+            HarnessState::Paused | HarnessState::Break(_) => {
                 if ui.button("Resume").clicked() {
                     self.state = HarnessState::Running;
                 }
@@ -66,7 +102,7 @@ impl<T: Bus + Clone> Harness<T> {
                     self.state = HarnessState::Paused;
                 }
             }
-            HarnessState::Error(_) => {
+            HarnessState::Halted(_) | HarnessState::Error(_) => {
                 if ui.button("Reset").clicked() {
                     self.cpu = self.old_cpu.clone();
                     self.state = HarnessState::Paused;
@@ -77,23 +113,166 @@ impl<T: Bus + Clone> Harness<T> {
             ui.label("Frequency:");
             ui.add(egui::DragValue::new(&mut self.frequency).clamp_range(60..=1000000));
         });
+        ui.checkbox(&mut self.cpu.cycle_stepped, "Cycle-stepped");
+        ui.checkbox(&mut self.cpu.undocumented_opcodes, "Undocumented opcodes");
+        ui.checkbox(&mut self.cpu.trap_self_jumps, "Halt on jumps to self");
+        ui.horizontal(|ui| {
+            if ui.button("Save snapshot").clicked() {
+                let saved = self
+                    .cpu
+                    .snapshot()
+                    .and_then(|snapshot| snapshot.save(&self.snapshot_path));
+                self.message = Some(match saved {
+                    Ok(()) => format!("Saved to {}", self.snapshot_path.display()),
+                    Err(e) => format!("Can't save snapshot: {e}"),
+                });
+            }
+            if ui.button("Load snapshot").clicked() {
+                let loaded = Snapshot::load(&self.snapshot_path)
+                    .and_then(|snapshot| self.cpu.restore(&snapshot));
+                self.message = Some(match loaded {
+                    Ok(()) => format!("Loaded {}", self.snapshot_path.display()),
+                    Err(e) => format!("Can't load snapshot: {e}"),
+                });
+            }
+        });
+        if let Some(message) = &self.message {
+            ui.label(message);
+        }
+        self.render_breakpoints(ui);
+        self.render_profiler(ui);
         self.cpu.render(ui);
     }
 
-    pub fn frame(&mut self, cpf: u32) {
-        if self.state.is_running() {
-            for _ in 0..cpf {
-                if let Err(e) = self.cpu.tick() {
-                    self.state = HarnessState::Error(e);
-                    break;
+    fn render_breakpoints(&mut self, ui: &mut Ui) {
+        ui.collapsing("Breakpoints", |ui| {
+            let mut removed = None;
+            let mut toggled = None;
+            for (id, breakpoint) in self.cpu.breakpoints.iter() {
+                ui.horizontal(|ui| {
+                    let mut enabled = breakpoint.enabled;
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        toggled = Some((id, enabled));
+                    }
+                    ui.label(format!(
+                        "{id}: {:?} ${:04X}-${:04X}, {} hits",
+                        breakpoint.kind,
+                        breakpoint.range.start(),
+                        breakpoint.range.end(),
+                        breakpoint.hits
+                    ));
+                    if ui.button("Remove").clicked() {
+                        removed = Some(id);
+                    }
+                });
+            }
+            if let Some((id, enabled)) = toggled {
+                self.cpu.breakpoints.set_enabled(id, enabled);
+            }
+            if let Some(id) = removed {
+                self.cpu.breakpoints.remove(id);
+            }
+
+            let (kind, start, end) = &mut self.new_breakpoint;
+            ui.horizontal(|ui| {
+                for (value, name) in [
+                    (BreakpointKind::Execute, "Execute"),
+                    (BreakpointKind::Read, "Read"),
+                    (BreakpointKind::Write, "Write"),
+                ] {
+                    ui.radio_value(kind, value, name);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(start).hexadecimal(4, false, true));
+                ui.label("to");
+                ui.add(egui::DragValue::new(end).hexadecimal(4, false, true));
+                if ui.button("Add").clicked() {
+                    let end = (*end).max(*start);
+                    self.cpu.breakpoints.add(*kind, *start..=end);
                 }
+            });
+        });
+    }
+
+    fn render_profiler(&mut self, ui: &mut Ui) {
+        ui.collapsing("Profiler", |ui| {
+            let mut enabled = self.cpu.profiler.is_some();
+            if ui.checkbox(&mut enabled, "Profile").changed() {
+                self.cpu.profiler = enabled.then(Profiler::new);
+                self.profile_refresh = 0;
+            }
+            let Some(profiler) = &mut self.cpu.profiler else {
+                return;
+            };
+            ui.horizontal(|ui| {
+                for (value, name) in [
+                    (SortBy::Address, "Address"),
+                    (SortBy::Count, "Count"),
+                    (SortBy::ExclusiveCycles, "Exclusive"),
+                    (SortBy::InclusiveCycles, "Inclusive"),
+                ] {
+                    if ui
+                        .radio_value(&mut self.profile_sort, value, name)
+                        .clicked()
+                    {
+                        self.profile_refresh = 0;
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Clear").clicked() {
+                    profiler.clear();
+                    self.profile_refresh = 0;
+                }
+                if ui.button("Save report").clicked() {
+                    let saved = fs::write(
+                        "profile.txt",
+                        profiler.report(self.profile_sort, usize::MAX, &self.labels),
+                    )
+                    .and_then(|()| {
+                        fs::write(
+                            "profile.json",
+                            profiler.to_json(self.profile_sort, &self.labels),
+                        )
+                    });
+                    self.message = Some(match saved {
+                        Ok(()) => "Saved profile.txt and profile.json".into(),
+                        Err(e) => format!("Can't save the profile: {e}"),
+                    });
+                }
+            });
+            if self.profile_refresh == 0 {
+                self.profile_report = profiler.report(self.profile_sort, 10, &self.labels);
+                self.profile_refresh = PROFILE_REFRESH_FRAMES;
             }
+            self.profile_refresh -= 1;
+            ui.monospace(&self.profile_report);
+        });
+    }
+
+    pub fn frame(&mut self, cpf: u32) {
+        if self.state.is_running() {
+            let result = self.cpu.run_cycles(cpf.into());
+            self.handle(result);
         }
     }
 
     pub fn single_step(&mut self) {
-        if let Err(e) = self.cpu.execute() {
-            self.state = HarnessState::Error(e);
+        let result = self.cpu.execute();
+        self.handle(result);
+    }
+
+    /// Updates the state after running the CPU. Returns whether it can keep going.
+    fn handle(&mut self, result: Result<Option<Break>, CPUError>) -> bool {
+        match result {
+            Ok(None) => match self.cpu.halted {
+                Some(halt) => self.state = HarnessState::Halted(halt),
+                None => return true,
+            },
+            Ok(Some(hit)) => self.state = HarnessState::Break(hit),
+            Err(e) => self.state = HarnessState::Error(e),
         }
+        false
     }
 }
@@ -2,7 +2,7 @@ use crate::snake::MCSnakeCanvas;
 use egui::{Color32, Frame, Style};
 use harness::Harness;
 use macroquad::prelude::*;
-use snake_game::snake_cpu;
+use snake_game::{snake_cpu, snake_labels};
 
 pub mod harness;
 pub mod snake;
@@ -30,6 +30,7 @@ async fn main() {
     let mut harness = Harness::new(snake_cpu::<MCSnakeCanvas>());
     harness.cpu.pc = 0x600;
     harness.frequency = 10000;
+    harness.labels = snake_labels();
     let texture = Texture2D::from_image(&harness.cpu.bus.canvas.image);
     texture.set_filter(FilterMode::Nearest);
     let mut draw_params = DrawTextureParams::default();
@@ -0,0 +1,24 @@
+//! Assembles `src/snake.asm`, so that a source that doesn't assemble fails the build, and writes
+//! the bytes and labels out for `src/lib.rs` to include.
+
+use std::fmt::Write;
+use std::{env, fs, path::Path};
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/snake.asm");
+    let source = fs::read_to_string("src/snake.asm").unwrap();
+    let program = match m6502_assembler::assemble(&source) {
+        Ok(program) => program,
+        Err(e) => panic!("src/snake.asm doesn't assemble: {e}"),
+    };
+    let mut out = String::new();
+    writeln!(out, "const ORIGIN: u16 = {:#06X};", program.origin).unwrap();
+    writeln!(out, "const BYTES: &[u8] = &{:?};", program.bytes).unwrap();
+    let labels: Vec<_> = program.labels().into_iter().collect();
+    writeln!(out, "const LABELS: &[(u16, &str)] = &{labels:?};").unwrap();
+    fs::write(
+        Path::new(&env::var("OUT_DIR").unwrap()).join("snake.rs"),
+        out,
+    )
+    .unwrap();
+}